@@ -21,6 +21,7 @@ use crate::{vminput::ActionPayload, vmgraphclip::VMGraphClip};
 
 pub const CURRENT_SAVE_FILE_VERSION: &str = "0.5.0";
 pub const CURRENT_CONFIG_FILE_VERSION: &str = "0.4.0";
+pub const CURRENT_RECENT_FILE_VERSION: &str = "0.1.0";
 
 pub const TEXT_HISTORY_SIZE: usize = 20;
 
@@ -36,7 +37,11 @@ pub const DEFAULT_STACK_Y_TOP_MARGIN: f64 = 25.;
 pub const DEFAULT_STACK_Y_BOTTOM_MARGIN: f64 = 10.;
 pub const DEFAULT_MARK_BORDER_WIDTH: f64 = 1.5;
 pub const DEFAULT_EDGE_WIDTH: f64 = 3.;
+pub const DEFAULT_ARROWHEAD_LENGTH: f64 = 14.;
+pub const DEFAULT_ARROWHEAD_WIDTH: f64 = 12.;
 pub const DEFAULT_LABEL_FONT_SIZE: f64 = 20.;
+pub const DEFAULT_EDGE_LABEL_FONT_SIZE: f64 = 14.;
+pub const DEFAULT_EDGE_LABEL_PADDING: f64 = 2.;
 pub const DEFAULT_SEARCH_TERM_FONT_SIZE: f64 = 16.;
 pub const DEFAULT_COMPOSE_INDICATOR_FONT_SIZE: f64 = 28.;
 pub const DIALOG_LABEL_BUTTON_SPACER: f64 = 40.;
@@ -45,6 +50,13 @@ pub const DEFAULT_COMPOSE_INDICATOR_INSET: Vec2 = Vec2::new(20., -20.);
 
 pub const DEFAULT_NODE_MASS: f64 = 10.;
 pub const DEFAULT_UPDATE_DELTA: f64 = 0.032;
+//Real time between simulation steps at a simulation speed of 1. Each step advances the simulation by
+// DEFAULT_UPDATE_DELTA whatever the display's refresh rate.
+pub const SIMULATION_STEP_INTERVAL: f64 = 1. / 60.;
+//Caps the steps taken in one frame so that a long stall doesn't freeze the UI while the simulation
+// catches up
+pub const MAX_SIMULATION_STEPS_PER_FRAME: usize = 8;
+pub const DEFAULT_SIMULATION_SPEED: f64 = 1.;
 pub const DEFAULT_SIMULATION_PARAMETERS: SimulationParameters = SimulationParameters {
     force_charge: 1000.,
     force_spring: 4.0,
@@ -52,15 +64,34 @@ pub const DEFAULT_SIMULATION_PARAMETERS: SimulationParameters = SimulationParame
     node_speed: 3000.,
     damping_factor: 0.5,
     min_attract_distance: 180.,
+    spring_length: 0.,
+    //Keep node boxes apart by a little more than their borders
+    collision_padding: 10.,
+    //Weak pulls that keep trees gathered around their roots and separate trees from drifting apart
+    component_gravity: 0.05,
+    center_gravity: 0.01,
+    //Approximate repulsion with a quadtree so large sheets stay responsive
+    barnes_hut_theta: Some(0.5),
+    seed: None,
 };
 
 pub const DEFAULT_MIN_NODE_WIDTH_DATA: f64 = 40.;
 pub const DEFUALT_TEXT_CURSOR_WIDTH: f64 = 1.5;
 
 pub const DEFAULT_MASS_INCREASE_AMOUNT: f64 = 2.;
+pub const DEFAULT_EDGE_LENGTH_STEP: f64 = 40.;
 pub const ANIMATION_MOVEMENT_THRESHOLD: f64 = 0.1;
+//Space left between neighboring nodes and between levels by the tree layouts
+pub const DEFAULT_TREE_LAYOUT_GAP: f64 = 40.;
+//Fraction of the remaining distance to their tree layout positions that nodes move each frame
+pub const DEFAULT_LAYOUT_EASING: f64 = 0.2;
 
 pub const DEFAULT_NODE_DRAG_THRESHOLD: f64 = 4.;
+//Width of the cells of the spatial index. Roughly the size of a node, so most nodes fall in one or two cells.
+pub const DEFAULT_SPATIAL_INDEX_CELL_SIZE: f64 = 200.;
+//Extra canvas space painted around the viewport so that parts drawn outside a node's box, like fold
+// badges and tag chips, don't pop in at the edges
+pub const DEFAULT_CULLING_MARGIN: f64 = 100.;
 
 pub const DEFAULT_PAN_AMOUNT_SMALL: f64 = 30.;
 pub const DEFAULT_PAN_AMOUNT_LARGE: f64 = 150.;
@@ -70,6 +101,21 @@ pub const DEFAULT_NODE_MOVE_AMOUNT_LARGE: f64 = 100.;
 
 pub const NODE_LABEL_MAX_CONSTRAINTS: (f64, f64) = (200., 115.);
 pub const BADGE_BORDER_INFLATION_AMOUNT: f64 = 3.;
+pub const MAX_TAGS_IN_CHIP: usize = 3;
+pub const DEFAULT_NOTE_PANEL_WIDTH: f64 = 360.;
+pub const DEFAULT_NOTE_PANEL_MIN_HEIGHT: f64 = 120.;
+pub const DEFAULT_NOTE_FONT_SIZE: f64 = 16.;
+pub const DEFAULT_MINIMAP_SIZE: (f64, f64) = (240., 180.);
+//Space kept between the outermost nodes and the edge of the minimap
+pub const DEFAULT_MINIMAP_PADDING: f64 = 8.;
+pub const DEFAULT_MINIMAP_NODE_RADIUS: f64 = 2.5;
+
+#[cfg(target_os = "windows")]
+pub const DEFAULT_LINK_OPENER: &str = "explorer";
+#[cfg(target_os = "macos")]
+pub const DEFAULT_LINK_OPENER: &str = "open";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub const DEFAULT_LINK_OPENER: &str = "xdg-open";
 
 pub const ACCEPTED_DELIMITERS: [(&'static str, &'static str); 6] = [
     (r#"""#, r#"""#), 
@@ -108,6 +154,8 @@ pub const DOUBLE_CLICK_THRESHOLD: Duration = Duration::from_millis(200);
 pub const DEFAULT_COMPOSE_TIMEOUT: Duration = Duration::from_millis(1150);
 pub const DEFAULT_BUILD_TIMEOUT: Duration = Duration::from_millis(1150);
 pub const DEFAULT_ANIMATION_TIMEOUT: Duration = Duration::from_millis(2000);
+//Interval at which the open sheet's file is polled for changes made outside of Vim-Mapper
+pub const DEFAULT_FILE_WATCH_INTERVAL: Duration = Duration::from_millis(1000);
 
 pub const SUBMIT_CHANGES: Selector = Selector::new("submit-changes");
 
@@ -134,6 +182,9 @@ pub const DEFAULT_SAVE_NAME: &str = "NewSheet.vmd";
 
 pub const DEFAULT_CONFIG_DIR_NAME: &str = "vim-mapper";
 pub const DEFAULT_CONFIG_FILE_NAME: &str = "vmconfig";
+pub const DEFAULT_RECENT_FILE_NAME: &str = "vmrecent";
+
+pub const MAX_RECENT_FILES: usize = 10;
 
 pub const DEFAULT_ROOT_LABEL: &str = "Root";
 
@@ -14,12 +14,15 @@
 
 #![windows_subsystem = "windows"]
 use druid::widget::{prelude::*, Flex};
-use druid::{AppLauncher, WindowDesc, FileDialogOptions, Point, WindowState, Command, Target, WidgetPod, LocalizedString, MenuItem, FileSpec, FontFamily, WindowId, Menu, AppDelegate};
+use druid::{AppLauncher, WindowDesc, FileDialogOptions, Point, WindowState, Command, Target, WidgetPod, LocalizedString, MenuItem, FileSpec, FontFamily, WindowId, Menu, AppDelegate, TimerToken};
+use druid::kurbo::TranslateScale;
 use druid::piet::{Text, TextLayout, TextLayoutBuilder};
 use vmdialog::{VMDialogParams, VMDialog, VMInputParams};
 use vmtabbar::VMTabBar;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::fs;
+use std::ffi::OsString;
 use std::path::{PathBuf, Path};
 use std::time::{SystemTime, UNIX_EPOCH};
 #[cfg(target_family = "windows")]
@@ -32,6 +35,13 @@ use windows::{
 };
 
 mod vmnode;
+use vmnode::VMNodeReference;
+
+mod vmedge;
+
+mod vmstyle;
+
+mod vmmarkdown;
 
 mod vminput;
 use vminput::*;
@@ -48,6 +58,9 @@ use vmconfig::*;
 mod vmsave;
 use vmsave::*;
 
+mod vmrecent;
+use vmrecent::*;
+
 mod vmgraphclip;
 use vmgraphclip::VMGraphClip;
 
@@ -59,6 +72,10 @@ mod vmtabbar;
 
 mod vmtextinput;
 
+mod vmspatial;
+
+mod vmsearch;
+
 struct VMCanvas {
     tabs: Vec<VMTab>,
     active_tab: usize,
@@ -75,6 +92,15 @@ struct VMCanvas {
     // Workaround for erroneous MouseUp mouse_event.pos when right-clicking tab bar, causing context menus
     // to break. Set on MouseDown, used and then unset on MouseUp.
     last_mouse_down_on_tab_bar: bool,
+    // Modified time of the file at path as of the last load or save. Polled on file_watch_token
+    // to detect changes made to the sheet outside of Vim-Mapper.
+    file_modified_time: Option<SystemTime>,
+    file_watch_token: TimerToken,
+    recent: VMRecentVersion1,
+    // The open action to run once a "Save As..." prompted by opening another sheet completes.
+    pending_open_action: Option<ActionPayload>,
+    // The node copied with "yr" along with its label, used to create reference nodes in other tabs.
+    node_reference_register: Option<(VMNodeReference, String)>,
 }
 
 pub struct VMTab {
@@ -83,12 +109,12 @@ pub struct VMTab {
 }
 
 impl VMCanvas {
-    pub fn new(config: VMConfigVersion4) -> VMCanvas {
+    pub fn new(config: VMConfigVersion4, recent: VMRecentVersion1) -> VMCanvas {
         VMCanvas {
             tabs: vec![],
             active_tab: 0,
             tab_bar: WidgetPod::new(VMTabBar::new(&config, &vec![], 0)),
-            dialog: VMCanvas::new_dialog(&config, VMDialog::make_start_dialog_params()),
+            dialog: VMCanvas::new_dialog(&config, VMDialog::make_start_dialog_params(&recent.files, recent.last_session.is_some())),
             dialog_visible: true,
             path: None,
             config,
@@ -98,6 +124,250 @@ impl VMCanvas {
             graph_clip_registers: HashMap::new(),
             debug_data: false,
             last_mouse_down_on_tab_bar: false,
+            file_modified_time: None,
+            file_watch_token: TimerToken::INVALID,
+            recent,
+            pending_open_action: None,
+            node_reference_register: None,
+        }
+    }
+
+    //Loads the sheet at path, replacing all open tabs, and moves it to the top of the recent files list.
+    // If the sheet can't be loaded it is dropped from the recent files list.
+    fn open_path(&mut self, path: PathBuf) -> Result<(), String> {
+        match VMSaveSerde::load(path.display().to_string()) {
+            Ok((save, path)) => {
+                let (tabs, active_tab) = VMSaveSerde::from_save(save, self.config.clone());
+                self.file_modified_time = VMCanvas::get_file_modified_time(&path);
+                self.path = Some(path.clone());
+                self.load_new_tabs(tabs, Some(active_tab));
+                self.add_recent_file(path);
+                return Ok(());
+            }
+            Err(err) => {
+                self.recent.remove_file(&path);
+                #[allow(unused_must_use)]
+                {
+                    VMRecentSerde::save(&self.recent);
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    fn add_recent_file(&mut self, path: PathBuf) {
+        self.recent.add_file(path);
+        #[allow(unused_must_use)]
+        {
+            VMRecentSerde::save(&self.recent);
+        }
+    }
+
+    //Records the open sheet and the viewport of its active tab so it can be reopened next launch.
+    fn record_last_session(&mut self) {
+        if let Some(path) = self.path.clone() {
+            if path.is_file() {
+                if let Some(tab) = self.tabs.get(self.active_tab) {
+                    let vm = tab.vm.widget();
+                    self.recent.last_session = Some(VMLastSession {
+                        path,
+                        active_tab: self.active_tab,
+                        zoom_index: vm.zoom_level_index,
+                        offset_x: vm.get_offset_x(),
+                        offset_y: vm.get_offset_y(),
+                    });
+                    #[allow(unused_must_use)]
+                    {
+                        VMRecentSerde::save(&self.recent);
+                    }
+                }
+            }
+        }
+    }
+
+    fn reopen_last_session(&mut self) -> Result<(), String> {
+        if let Some(session) = self.recent.last_session.clone() {
+            self.open_path(session.path.clone())?;
+            if session.active_tab < self.tabs.len() {
+                self.active_tab = session.active_tab;
+            }
+            let vm = self.tabs[self.active_tab].vm.widget_mut();
+            if session.zoom_index < ZOOM_LEVELS.len() {
+                vm.zoom_level_index = session.zoom_index;
+                vm.scale = TranslateScale::scale(ZOOM_LEVELS[session.zoom_index]);
+            }
+            vm.set_offset_x(session.offset_x);
+            vm.set_offset_y(session.offset_y);
+            let tab_names: Vec<String> = self.tabs.iter().map(|v| -> String {return v.tab_name.clone()}).collect();
+            self.tab_bar.widget_mut().update_tabs(&tab_names, self.active_tab);
+            return Ok(());
+        } else {
+            return Err(String::from("No previous session to reopen."));
+        }
+    }
+
+    //Opens a node link. Links to .vmd sheets, optionally followed by #<mark>, are opened in VimMapper
+    // and the marked node is activated. Anything else is handed to the configured link opener. Relative
+    // paths are resolved from the open sheet's folder, while URLs are passed on unchanged.
+    fn open_link(&mut self, ctx: &mut EventCtx, link: String) {
+        let (target, mark) = match link.rsplit_once('#') {
+            Some((target, mark)) if target.ends_with(".vmd") => (target.to_string(), Some(mark.to_string())),
+            _ => (link.clone(), None),
+        };
+        if target.ends_with(".vmd") && !VMCanvas::is_url(&target) {
+            let path = self.resolve_link_path(&target);
+            let is_open_sheet = self.path.as_ref().map_or(false, |current| {
+                fs::canonicalize(current).ok() == fs::canonicalize(&path).ok()
+            });
+            if is_open_sheet {
+                if let Some(mark) = mark {
+                    ctx.submit_command(Command::new(
+                        EXECUTE_ACTION,
+                        ActionPayload {
+                            action: Action::JumpToMarkedNode,
+                            string: Some(mark),
+                            ..Default::default()
+                        },
+                        Target::Global
+                    ));
+                }
+            } else {
+                ctx.submit_command(Command::new(
+                    EXECUTE_ACTION,
+                    ActionPayload {
+                        action: Action::OpenRecentSheet,
+                        path: Some(path),
+                        string: mark,
+                        ..Default::default()
+                    },
+                    Target::Global
+                ));
+            }
+        } else {
+            let opener = self.config.get_link_opener();
+            let mut parts = opener.split_whitespace();
+            let target = if VMCanvas::is_url(&link) {
+                OsString::from(&link)
+            } else {
+                self.resolve_link_path(&link).into_os_string()
+            };
+            if let Some(program) = parts.next() {
+                if let Err(err) = std::process::Command::new(program).args(parts).arg(&target).spawn() {
+                    tracing::error!("Couldn't open {} with {}: {}", link, program, err);
+                }
+            }
+        }
+    }
+
+    //Resolves a relative link path from the folder of the open sheet.
+    fn resolve_link_path(&self, link: &str) -> PathBuf {
+        let path = PathBuf::from(link);
+        if path.is_relative() {
+            if let Some(dir) = self.path.as_ref().and_then(|current| current.parent()) {
+                return dir.join(path);
+            }
+        }
+        return path;
+    }
+
+    //Whether the link starts with a URL scheme such as http: or mailto:. A single letter followed by a
+    // colon is taken to be a Windows drive rather than a scheme.
+    fn is_url(link: &str) -> bool {
+        return link.split_once(':').map_or(false, |(scheme, _)| {
+            return scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+        });
+    }
+
+    fn quit(&mut self, ctx: &mut EventCtx) {
+        self.record_last_session();
+        ctx.submit_command(druid::commands::QUIT_APP);
+    }
+
+    fn make_start_dialog_params(&self) -> VMDialogParams {
+        return VMDialog::make_start_dialog_params(&self.recent.files, self.recent.last_session.is_some());
+    }
+
+    fn get_file_modified_time(path: &PathBuf) -> Option<SystemTime> {
+        if let Ok(metadata) = fs::metadata(path) {
+            return metadata.modified().ok();
+        } else {
+            return None;
+        }
+    }
+
+    //Saves all tabs to path and records the new modified time so that the file watcher doesn't
+    // mistake our own save for an external change.
+    fn save_tabs(&mut self, path: PathBuf) -> Result<String, String> {
+        let result = VMSaveSerde::save(
+            &VMSaveSerde::to_save(&self.tabs, self.active_tab),
+            path.clone(),
+        );
+        if let Ok(_) = result {
+            self.file_modified_time = VMCanvas::get_file_modified_time(&path);
+        }
+        return result;
+    }
+
+    //Reloads the sheet at path in place. The active tab and the viewport of each tab are carried
+    // over to the reloaded tabs where a tab with the same name still exists. Other tabs open with the
+    // viewport they were saved with.
+    fn reload_from_disk(&mut self, ctx: &mut EventCtx) -> Result<(), String> {
+        if let Some(path) = self.path.clone() {
+            let (save, path) = VMSaveSerde::load(path.display().to_string())?;
+            let (mut tabs, _) = VMSaveSerde::from_save(save, self.config.clone());
+            //Tabs that share a name are paired up in order
+            let mut matched_tabs: Vec<usize> = vec![];
+            let mut active_tab = 0;
+            for (index, tab) in tabs.iter_mut().enumerate() {
+                let old_index = (0..self.tabs.len())
+                    .find(|old_index| self.tabs[*old_index].tab_name == tab.tab_name && !matched_tabs.contains(old_index));
+                if let Some(old_index) = old_index {
+                    matched_tabs.push(old_index);
+                    if old_index == self.active_tab {
+                        active_tab = index;
+                    }
+                    let old_vm = self.tabs[old_index].vm.widget();
+                    let vm = tab.vm.widget_mut();
+                    vm.translate = old_vm.translate;
+                    vm.scale = old_vm.scale;
+                    vm.offset_x = old_vm.offset_x;
+                    vm.offset_y = old_vm.offset_y;
+                    vm.zoom_level_index = old_vm.zoom_level_index;
+                }
+                tab.vm.widget_mut().debug_data = self.debug_data;
+            }
+            self.load_new_tabs(tabs, Some(active_tab));
+            self.file_modified_time = VMCanvas::get_file_modified_time(&path);
+            ctx.children_changed();
+            ctx.request_layout();
+            return Ok(());
+        } else {
+            return Err(String::from("Sheet has no path to reload from."));
+        }
+    }
+
+    fn check_for_external_changes(&mut self, ctx: &mut EventCtx, data: &mut AppState) {
+        if self.dialog_visible {
+            return;
+        }
+        if let Some(path) = self.path.clone() {
+            let modified_time = VMCanvas::get_file_modified_time(&path);
+            if modified_time.is_some() && self.file_modified_time.is_some() && modified_time != self.file_modified_time {
+                self.file_modified_time = modified_time;
+                match data.save_state {
+                    VMSaveState::Saved => {
+                        if let Err(err) = self.reload_from_disk(ctx) {
+                            tracing::error!("Couldn't reload sheet after external change: {}", err);
+                        }
+                    }
+                    VMSaveState::UnsavedChanges => {
+                        self.set_dialog(ctx, data, VMDialog::make_external_change_dialog_params(), true);
+                    }
+                    _ => ()
+                }
+            }
         }
     }
 
@@ -183,7 +453,13 @@ impl VMCanvas {
 
     fn delete_tab(&mut self, ctx: &mut EventCtx, tab_id: usize) {
         if let Some(_) = self.tabs.get(tab_id) {
-            self.tabs.remove(tab_id);
+            let removed = self.tabs.remove(tab_id);
+            //References to the deleted tab can't be followed anymore unless another tab has the same name
+            if !self.tabs.iter().any(|tab| tab.tab_name == removed.tab_name) {
+                for tab in &mut self.tabs {
+                    tab.vm.widget_mut().break_references(&removed.tab_name);
+                }
+            }
             if tab_id == self.active_tab {
                 if self.active_tab > self.tabs.len() - 1 {
                     self.active_tab = self.active_tab - 1;
@@ -277,10 +553,28 @@ impl VMCanvas {
                     Action::IncreaseNodeMass |
                     Action::DecreaseNodeMass |
                     Action::ResetNodeMass |
+                    Action::LengthenTargetEdge |
+                    Action::ShortenTargetEdge |
+                    Action::ResetTargetEdgeLength |
                     Action::ToggleNodeAnchor |
                     Action::EditActiveNodeSelectAll |
                     Action::EditActiveNodeAppend |
                     Action::EditActiveNodeInsert |
+                    Action::EditTargetEdge |
+                    Action::EditActiveNodeNote |
+                    Action::ToggleTargetEdgeArrow |
+                    Action::ToggleActiveEdgeArrow |
+                    Action::LinkTargetNode |
+                    Action::LinkMarkedNode |
+                    Action::UnlinkTargetNode |
+                    Action::ApplyNodeStyle |
+                    Action::SetNodeMetadata |
+                    Action::SetNodeLink |
+                    Action::PasteNodeReference |
+                    Action::ToggleNodeFold |
+                    Action::OpenNodeFold |
+                    Action::CloseNodeFold |
+                    Action::CycleLayoutMode |
                     Action::AttemptNodeDeletion |
                     Action::CutNode |
                     Action::CutTargetNode |
@@ -339,7 +633,7 @@ impl VMCanvas {
                             ));
                         }
                     } else {
-                        self.set_dialog(ctx, data, VMDialog::make_start_dialog_params(), self.dialog_visible);
+                        self.set_dialog(ctx, data, self.make_start_dialog_params(), self.dialog_visible);
                     }
                     ctx.set_focus(ctx.widget_id());
                     return Ok(());
@@ -356,13 +650,68 @@ impl VMCanvas {
                         ctx.set_handled();
                         return Ok(());
                     } else if data.save_state == VMSaveState::NoSave {
-                        self.set_dialog(ctx, data, VMDialog::make_save_as_and_open_dialog_params(), true);
+                        self.pending_open_action = Some(payload.clone());
+                        self.set_dialog(ctx, data, VMDialog::make_save_as_and_open_dialog_params(payload.clone()), true);
                         return Ok(());
                     } else {
-                        self.set_dialog(ctx, data, VMDialog::make_save_and_open_dialog_params(), true);
+                        self.set_dialog(ctx, data, VMDialog::make_save_and_open_dialog_params(payload.clone()), true);
                         return Ok(());
                     }
                 }
+                Action::OpenRecentSheet | Action::ReopenLastSession => {
+                    if data.save_state == VMSaveState::Saved || data.save_state == VMSaveState::NoSheetOpened || data.save_state == VMSaveState::DiscardChanges {
+                        let result = if payload.action == Action::ReopenLastSession {
+                            self.reopen_last_session()
+                        } else if let Some(path) = payload.path.clone() {
+                            self.open_path(path)
+                        } else {
+                            Err(String::from("No path given to open."))
+                        };
+                        match result {
+                            Ok(_) => {
+                                data.save_state = VMSaveState::Saved;
+                                ctx.children_changed();
+                                ctx.request_layout();
+                                //Sheet links can name a mark to jump to once the sheet is open
+                                if let Some(mark) = payload.string.clone() {
+                                    ctx.submit_command(Command::new(
+                                        EXECUTE_ACTION,
+                                        ActionPayload {
+                                            action: Action::JumpToMarkedNode,
+                                            string: Some(mark),
+                                            ..Default::default()
+                                        },
+                                        Target::Global
+                                    ));
+                                }
+                            }
+                            Err(err) => {
+                                tracing::error!("Couldn't open sheet: {}", err);
+                                match data.save_state {
+                                    VMSaveState::NoSheetOpened => {
+                                        self.set_dialog(ctx, data, self.make_start_dialog_params(), true);
+                                    }
+                                    VMSaveState::DiscardChanges => {
+                                        if let Some(_) = self.path {
+                                            data.save_state = VMSaveState::UnsavedChanges;
+                                        } else {
+                                            data.save_state = VMSaveState::NoSave;
+                                        }
+                                    }
+                                    _ => ()
+                                }
+                            }
+                        }
+                        data.recent_files = Arc::new(self.recent.get_file_strings());
+                    } else if data.save_state == VMSaveState::NoSave {
+                        self.pending_open_action = Some(payload.clone());
+                        self.set_dialog(ctx, data, VMDialog::make_save_as_and_open_dialog_params(payload.clone()), true);
+                    } else {
+                        self.set_dialog(ctx, data, VMDialog::make_save_and_open_dialog_params(payload.clone()), true);
+                    }
+                    ctx.set_handled();
+                    return Ok(());
+                }
                 Action::CreateNewSheet => {
                     if data.save_state == VMSaveState::Saved || data.save_state == VMSaveState::NoSheetOpened || data.save_state == VMSaveState::DiscardChanges {
                         // self.load_new_mapper(VimMapper::new(self.config.clone()));
@@ -399,10 +748,7 @@ impl VMCanvas {
                     let tab = &mut self.tabs.get_mut(self.active_tab);
                     if let Some(_) = tab {
                         if let Some(path) = self.path.clone() {
-                            if let Ok(_) = VMSaveSerde::save(
-                                &VMSaveSerde::to_save(&self.tabs, self.active_tab),
-                                path,
-                            ) {
+                            if let Ok(_) = self.save_tabs(path) {
                                     data.save_state = VMSaveState::Saved;
                             }
                         } else {
@@ -434,11 +780,18 @@ impl VMCanvas {
                     }
                 }
                 Action::QuitWithoutSaveGuard => {
-                    ctx.submit_command(druid::commands::QUIT_APP);
+                    self.quit(ctx);
                 }
                 Action::SetSaveState => {
                     data.save_state = payload.save_state.clone().unwrap();
                 }
+                Action::ReloadSheetFromDisk => {
+                    if let Ok(_) = self.reload_from_disk(ctx) {
+                        data.save_state = VMSaveState::Saved;
+                    }
+                    ctx.set_handled();
+                    return Ok(());
+                }
                 _ => ()
             }
         }
@@ -531,6 +884,87 @@ impl VMCanvas {
                         );
                         return Ok(());
                     },
+                    Action::OpenNodeMetadataInput => {
+                        let metadata = tab.vm.widget().get_active_node_metadata_string();
+                        self.set_input_dialog(ctx, data, VMDialog::make_node_metadata_input_params(),
+                            metadata,
+                            true
+                        );
+                        return Ok(());
+                    },
+                    Action::OpenNodeLinkInput => {
+                        let link = tab.vm.widget().get_active_node_link().unwrap_or_default();
+                        self.set_input_dialog(ctx, data, VMDialog::make_node_link_input_params(),
+                            link,
+                            true
+                        );
+                        return Ok(());
+                    },
+                    Action::OpenSimulationParametersInput => {
+                        let parameters = tab.vm.widget().get_simulation_parameters_string();
+                        self.set_input_dialog(ctx, data, VMDialog::make_simulation_parameters_input_params(None),
+                            parameters,
+                            true
+                        );
+                        return Ok(());
+                    },
+                    Action::SetSimulationParameters => {
+                        let text = payload.string.clone().unwrap_or_default();
+                        match tab.vm.widget_mut().set_simulation_parameters_from_string(&text) {
+                            Ok(()) => data.save_state = VMSaveState::UnsavedChanges,
+                            //Reopen the editor with the problem shown so the entry can be corrected
+                            Err(message) => self.set_input_dialog(ctx, data, VMDialog::make_simulation_parameters_input_params(Some(message)),
+                                text,
+                                true
+                            ),
+                        }
+                        return Ok(());
+                    },
+                    Action::OpenNodeLink => {
+                        if let Some(link) = tab.vm.widget().get_active_node_link() {
+                            self.open_link(ctx, link);
+                        }
+                        return Ok(());
+                    },
+                    Action::CopyNodeReference => {
+                        let vm = tab.vm.widget();
+                        if let Some(idx) = vm.get_active_node_idx() {
+                            self.node_reference_register = Some((
+                                VMNodeReference {
+                                    tab_name: tab.tab_name.clone(),
+                                    node_index: idx,
+                                    broken: false,
+                                },
+                                vm.get_nodes()[&idx].get_label(),
+                            ));
+                        }
+                        return Ok(());
+                    },
+                    Action::PasteNodeReference => {
+                        if let Some((reference, label)) = self.node_reference_register.clone() {
+                            tab.vm.widget_mut().add_reference_node(reference, label);
+                            ctx.request_layout();
+                        }
+                        return Ok(());
+                    },
+                    Action::FollowNodeReference => {
+                        if let Some(reference) = tab.vm.widget().get_active_node_reference() {
+                            let target_tab = self.tabs.iter().position(|tab| tab.tab_name == reference.tab_name)
+                                .filter(|target_tab| self.tabs[*target_tab].vm.widget().get_nodes().contains_key(&reference.node_index));
+                            self.tabs[self.active_tab].vm.widget_mut().set_active_node_reference_broken(target_tab.is_none());
+                            if let Some(target_tab) = target_tab {
+                                self.tabs[target_tab].vm.widget_mut().reveal_node(reference.node_index);
+                                self.active_tab = target_tab;
+                                ctx.children_changed();
+                                let tab_names: Vec<String> = self.tabs.iter().map(|v| -> String {return v.tab_name.clone()}).collect();
+                                self.tab_bar.widget_mut().update_tabs(&tab_names, self.active_tab);
+                            } else {
+                                tracing::warn!("Referenced node {} in tab \"{}\" no longer exists", reference.node_index, reference.tab_name);
+                            }
+                            ctx.request_layout();
+                        }
+                        return Ok(());
+                    },
                     Action::OpenRenameTabInput => {
                         self.set_input_dialog(ctx, data, VMDialog::make_rename_tab_prompt_input_params(),
                             String::from(format!("Tab {}", self.active_tab + 1)), 
@@ -539,7 +973,11 @@ impl VMCanvas {
                         return Ok(());
                     },
                     Action::RenameTab => {
+                        let old_name = tab.tab_name.clone();
                         tab.tab_name = payload.string.clone().unwrap();
+                        for tab in &mut self.tabs {
+                            tab.vm.widget_mut().rename_references(&old_name, &payload.string.clone().unwrap());
+                        }
                         let tab_names: Vec<String> = self.tabs.iter().map(|v| -> String {return v.tab_name.clone()}).collect();
                         self.tab_bar.widget_mut().update_tabs(&tab_names, self.active_tab);
                         return Ok(());
@@ -644,6 +1082,22 @@ impl VMCanvas {
         if data.menu_visible { 
             let base = Menu::<AppState>::empty();
 
+            let mut recent_menu = Menu::new(LocalizedString::new("recent-menu").with_placeholder("Open Recent"));
+            for file in data.recent_files.iter() {
+                recent_menu = recent_menu.entry(
+                    MenuItem::new(file.clone())
+                    .command(Command::new(
+                        EXECUTE_ACTION,
+                        ActionPayload {
+                            action: Action::OpenRecentSheet,
+                            path: Some(PathBuf::from(file)),
+                            ..Default::default()
+                        },
+                        Target::Global,
+                    ))
+                );
+            }
+
             let file_menu = Menu::new(LocalizedString::new("file-menu").with_placeholder("File"))
             .entry(
                 MenuItem::new(
@@ -672,6 +1126,20 @@ impl VMCanvas {
                     Target::Global,
                 ))
             )
+            .entry(recent_menu)
+            .entry(
+                MenuItem::new(
+                    String::from("Reopen Last Session")
+                )
+                .command(Command::new(
+                    EXECUTE_ACTION,
+                    ActionPayload {
+                        action: Action::ReopenLastSession,
+                        ..Default::default()
+                    },
+                    Target::Global,
+                ))
+            )
             .entry(
                 MenuItem::new(
                     String::from("Save\tCtrl+S")
@@ -708,7 +1176,7 @@ impl VMCanvas {
                 .command(druid::commands::CLOSE_ALL_WINDOWS)
             );
             return base.entry(file_menu).rebuild_on(|old_data, data, _env| {
-                old_data.menu_visible != data.menu_visible
+                old_data.menu_visible != data.menu_visible || !old_data.recent_files.same(&data.recent_files)
             });
         } else {
             let base = Menu::<AppState>::empty();
@@ -735,11 +1203,9 @@ impl Widget<AppState> for VMCanvas {
             }
             Event::Command(command) if command.is(druid::commands::OPEN_FILE) => {
                 let payload = command.get_unchecked(druid::commands::OPEN_FILE);
-                if let Ok((save, path)) = VMSaveSerde::load(payload.path().to_str().unwrap().to_string()) {
-                    let (tabs, active_tab) = VMSaveSerde::from_save(save, self.config.clone());
-                    self.path = Some(path);
-                    self.load_new_tabs(tabs, Some(active_tab));
+                if let Ok(_) = self.open_path(payload.path().to_path_buf()) {
                     data.save_state = VMSaveState::Saved;
+                    data.recent_files = Arc::new(self.recent.get_file_strings());
                     ctx.children_changed();
                 }
             }
@@ -753,30 +1219,23 @@ impl Widget<AppState> for VMCanvas {
                     let res = self.set_path(payload.path().to_path_buf());
                     // let inner = &self.tabs.get(self.active_tab).unwrap().vm;
                     if let Ok(path) = res {
+                        self.add_recent_file(path.clone());
+                        data.recent_files = Arc::new(self.recent.get_file_strings());
                         match data.save_state {
                             VMSaveState::UnsavedChanges => {
                                 data.save_state = VMSaveState::Saved;
                                 // VMSaveSerde::save(&VMSaveSerde::to_save(inner.widget()), path);
-                                VMSaveSerde::save(
-                                    &VMSaveSerde::to_save(&self.tabs, self.active_tab),
-                                    path
-                                );
+                                self.save_tabs(path);
                             },
                             VMSaveState::SaveAsInProgress => {
                                 data.save_state = VMSaveState::Saved;
                                 // VMSaveSerde::save(&VMSaveSerde::to_save(inner.widget()), path);
-                                VMSaveSerde::save(
-                                    &VMSaveSerde::to_save(&self.tabs, self.active_tab),
-                                    path
-                                );
+                                self.save_tabs(path);
                             },
                             VMSaveState::SaveAsInProgressThenQuit => {
                                 data.save_state = VMSaveState::Saved;
                                 // VMSaveSerde::save(&VMSaveSerde::to_save(inner.widget()), path);
-                                VMSaveSerde::save(
-                                    &VMSaveSerde::to_save(&self.tabs, self.active_tab),
-                                    path
-                                );
+                                self.save_tabs(path);
                                 ctx.submit_command(Command::new(
                                     EXECUTE_ACTION,
                                     ActionPayload {
@@ -789,10 +1248,7 @@ impl Widget<AppState> for VMCanvas {
                             VMSaveState::SaveAsInProgressThenNew => {
                                 data.save_state = VMSaveState::Saved;
                                 // VMSaveSerde::save(&VMSaveSerde::to_save(inner.widget()), path);
-                                VMSaveSerde::save(
-                                    &VMSaveSerde::to_save(&self.tabs, self.active_tab),
-                                    path
-                                );
+                                self.save_tabs(path);
                                 ctx.submit_command(Command::new(
                                     EXECUTE_ACTION,
                                     ActionPayload {
@@ -805,26 +1261,20 @@ impl Widget<AppState> for VMCanvas {
                             VMSaveState::SaveAsInProgressThenOpen => {
                                 data.save_state = VMSaveState::Saved;
                                 // VMSaveSerde::save(&VMSaveSerde::to_save(inner.widget()), path);
-                                VMSaveSerde::save(
-                                    &VMSaveSerde::to_save(&self.tabs, self.active_tab),
-                                    path
-                                );
+                                self.save_tabs(path);
                                 ctx.submit_command(Command::new(
                                     EXECUTE_ACTION,
-                                    ActionPayload {
+                                    self.pending_open_action.take().unwrap_or(ActionPayload {
                                         action: Action::OpenExistingSheet,
                                         ..Default::default()
-                                    },
+                                    }),
                                     Target::Global
                                 ));
                             },
                             VMSaveState::Saved => {
                                 data.save_state = VMSaveState::Saved;
                                 // VMSaveSerde::save(&VMSaveSerde::to_save(inner.widget()), path);
-                                VMSaveSerde::save(
-                                    &VMSaveSerde::to_save(&self.tabs, self.active_tab),
-                                    path
-                                );
+                                self.save_tabs(path);
                             },
                             _ => {
                                 tracing::error!("Tried to resolve SaveAs with an invalid save_state!");
@@ -840,7 +1290,7 @@ impl Widget<AppState> for VMCanvas {
                 match data.save_state {
                     VMSaveState::NoSheetOpened => {
                         self.take_focus = true;
-                        self.set_dialog(ctx, data, VMDialog::make_start_dialog_params(), true);
+                        self.set_dialog(ctx, data, self.make_start_dialog_params(), true);
                     },
                     VMSaveState::DiscardChanges => {
                         if let Some(_) = self.path {
@@ -890,9 +1340,9 @@ impl Widget<AppState> for VMCanvas {
                 if self.dialog_visible && data.save_state != VMSaveState::NoSheetOpened {
                 } else {
                     match data.save_state {
-                        VMSaveState::NoSheetOpened => ctx.submit_command(druid::commands::QUIT_APP),
-                        VMSaveState::Saved => ctx.submit_command(druid::commands::QUIT_APP),
-                        VMSaveState::DiscardChanges => ctx.submit_command(druid::commands::QUIT_APP),
+                        VMSaveState::NoSheetOpened => self.quit(ctx),
+                        VMSaveState::Saved => self.quit(ctx),
+                        VMSaveState::DiscardChanges => self.quit(ctx),
                         VMSaveState::NoSave => {
                             self.set_dialog(ctx, data, VMDialog::make_save_as_and_quit_dialog_params(), true);
                         },
@@ -995,6 +1445,10 @@ impl Widget<AppState> for VMCanvas {
                     } 
                 }
             },
+            Event::Timer(token) if *token == self.file_watch_token => {
+                self.check_for_external_changes(ctx, data);
+                self.file_watch_token = ctx.request_timer(DEFAULT_FILE_WATCH_INTERVAL);
+            },
             Event::Timer(token) => {
                 let mut im = &mut self.start_input_manager;
                 if let Some(tab) = self.tabs.get_mut(self.active_tab) {
@@ -1008,6 +1462,7 @@ impl Widget<AppState> for VMCanvas {
             },
             Event::WindowConnected => {
                 ctx.request_focus();
+                self.file_watch_token = ctx.request_timer(DEFAULT_FILE_WATCH_INTERVAL);
                 if let Some(menu_visible) = self.config.menu_shown {
                     data.menu_visible = menu_visible;
                 }
@@ -1199,6 +1654,7 @@ pub struct AppState {
     menu_visible: bool,
     save_state: VMSaveState,
     dialog_input_text: String,
+    recent_files: Arc<Vec<String>>,
 }
 
 struct Delegate;
@@ -1268,13 +1724,14 @@ pub fn main() {
     };
 
     let mut canvas;
+    let recent = VMRecentSerde::load();
     match VMConfigSerde::load() {
         Ok(config) => {
-            canvas = VMCanvas::new(config);
+            canvas = VMCanvas::new(config, recent);
         }
         Err((err, config)) => {
             println!("{}", err);
-            canvas = VMCanvas::new(config);
+            canvas = VMCanvas::new(config, recent);
         }
     }
 
@@ -1286,11 +1743,7 @@ pub fn main() {
         if path.exists() {
             if let Some(ext) = path.extension() {
                 if ext == "vmd" {
-                    if let Ok((save, path)) = VMSaveSerde::load(path.display().to_string()) {
-                        let (tabs, active_tab) = VMSaveSerde::from_save(save, canvas.config.clone());
-                        canvas.path = Some(path.clone());
-                        // canvas.load_new_mapper(vm);
-                        canvas.load_new_tabs(tabs, Some(active_tab));
+                    if let Ok(_) = canvas.open_path(path.to_path_buf()) {
                         launch_with_file = true;
                         println!("Launching with open sheet: {}...", path.display());
                     }
@@ -1309,7 +1762,13 @@ pub fn main() {
                 println!("Cannot create a new file without a .vmd extension!");
             }
         }
+    } else if canvas.config.get_reopen_last_session() {
+        if let Ok(_) = canvas.reopen_last_session() {
+            launch_with_file = true;
+            println!("Reopening last session...");
+        }
     }
+    let recent_files = Arc::new(canvas.recent.get_file_strings());
 
 
     let window = WindowDesc::<AppState>::new(canvas)
@@ -1329,7 +1788,8 @@ pub fn main() {
         } else {
             VMSaveState::NoSheetOpened
         },
-        dialog_input_text: String::from("")
+        dialog_input_text: String::from(""),
+        recent_files: recent_files.clone(),
     })
     .expect("launch failed");
     #[cfg(not(debug_assertions))]
@@ -1354,7 +1814,8 @@ pub fn main() {
         } else {
             VMSaveState::NoSheetOpened
         },
-        dialog_input_text: String::from("")
+        dialog_input_text: String::from(""),
+        recent_files: recent_files.clone(),
     })
     .expect("launch failed");
     }
@@ -13,20 +13,27 @@
 // limitations under the License.
 
 use common_macros::hash_set;
-use druid::kurbo::{Line, TranslateScale};
+use druid::kurbo::{Circle, Line, TranslateScale};
 use druid::piet::{Text, TextLayoutBuilder, TextLayout, PietText, TextAttribute};
 use druid::piet::PietTextLayout;
-use vm_force_graph_rs::{ForceGraph, NodeData, EdgeData, DefaultNodeIdx};
+use vm_force_graph_rs::{ForceGraph, NodeData, EdgeData, DefaultNodeIdx, LayoutMode};
+use petgraph::visit::Bfs;
 use druid::widget::prelude::*;
 use druid::{Color, FontFamily, Affine, Point, Vec2, Rect, TimerToken, Command, Target, Menu, MenuItem, FontWeight, MouseButton};
 use regex::Regex;
-use std::collections::{HashMap};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::f64::consts::*;
 
 use crate::vmdialog::VMDialog;
 use crate::vmgraphclip::VMGraphClip;
 use crate::{vminput::*, AppState};
-use crate::vmnode::VMNode;
+use crate::vmnode::{VMNode, VMNodeReference};
+use crate::vmstyle::VMNodeStyle;
+use crate::vmmarkdown::{InlineSpan, InlineStyle, parse_inline_markdown, apply_inline_spans};
+use crate::vmsearch::{SearchMatcher, is_fuzzy_search};
+use crate::vmedge::{VMEdge, EdgeDirection, clip_to_rounded_rect, build_arrowhead};
+use crate::vmspatial::{VMSpatialIndex, line_may_cross};
 
 use crate::constants::*;
 
@@ -48,8 +55,10 @@ pub struct VimMapper {
     pub(crate) nodes: HashMap<u32, VMNode>,
     //The global index count that provides new nodes with a unique u32 key.
     pub(crate) node_idx_count: u32,
-    // //The global index count that provides new edges with a unique u32 key.
-    // pub(crate) edge_idx_count: u32,
+    //The global map of edges, keyed by the user_data of the corresponding ForceGraph EdgeData.
+    pub(crate) edges: HashMap<u32, VMEdge>,
+    //The global index count that provides new edges with a unique u32 key.
+    pub(crate) edge_idx_count: u32,
     //The translate portion of the canvas transform. This pans the canvas. Updated only during paints.
     pub(crate) translate: TranslateScale,
     //The scale portion of the canvas transform. This zooms the canvas. These two transforms are
@@ -78,6 +87,9 @@ pub struct VimMapper {
     //Stores the largest individual movement (in either x or y) of any nodes during an update.
     // Used to pause computation once the graph has stabilized. 
     pub(crate) largest_node_movement: Option<f64>,
+    //Seconds of frame time that haven't yet been spent on simulation steps. Steps are taken at a fixed
+    // rate regardless of how often frames arrive, so the layout settles the same way at any refresh rate.
+    pub(crate) simulation_time: f64,
     // Cached dimensions of the screen. Used to compute the offsets required to scroll a given
     // Rect into view.
     pub(crate) canvas_rect: Option<Rect>,
@@ -92,6 +104,29 @@ pub struct VimMapper {
 
     pub(crate) enabled_layouts: HashMap<DefaultNodeIdx, PietTextLayout>,
     pub(crate) disabled_layouts: HashMap<DefaultNodeIdx, PietTextLayout>,
+    pub(crate) edge_layouts: HashMap<u32, PietTextLayout>,
+    //The edge whose label is being edited. While set, text actions are routed to this edge's
+    // text input instead of the active node's.
+    pub(crate) edited_edge: Option<u32>,
+    //The node whose note is open in the notes panel. Like edited_edge, text actions are routed to this
+    // node's note input while it's set.
+    pub(crate) edited_note: Option<u32>,
+    //Set whenever the graph changes shape so that the nodes hidden by folds are recomputed on the next
+    // layout.
+    pub(crate) folds_stale: bool,
+    //How the nodes of this tab are arranged. Tree layouts replace the force simulation and animate the
+    // nodes toward positions computed from the root nodes.
+    pub(crate) layout_mode: LayoutMode,
+    //Whether the minimap is painted over this tab
+    pub(crate) minimap_shown: bool,
+    //Where the minimap was last painted along with the transform it used from canvas space. Used to
+    // map clicks in the minimap back onto the sheet.
+    pub(crate) minimap: Option<(Rect, TranslateScale)>,
+    //Set while a click that began in the minimap is held, so that dragging keeps panning the sheet
+    pub(crate) minimap_dragging: bool,
+    //Canvas space rects of the visible nodes, rebuilt on every layout. Used for hit testing and to skip
+    // painting nodes and edges that are off screen.
+    pub(crate) spatial_index: VMSpatialIndex,
 
     pub(crate) root_nodes: HashMap<usize, DefaultNodeIdx>,
 
@@ -106,6 +141,13 @@ pub struct VimMapper {
     
     pub(crate) zoom_level_index: usize,
     pub(crate) search_string: String,
+    //Why the last search string couldn't be used, shown in place of its results. Only set for invalid
+    // regexes.
+    pub(crate) search_error: Option<String>,
+    //Byte ranges of the characters in each result's rendered label that matched the last search, and the
+    // label layouts built with those characters highlighted for the results list.
+    pub(crate) search_matches: HashMap<u32, Vec<Range<usize>>>,
+    pub(crate) search_layouts: HashMap<u32, PietTextLayout>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -151,6 +193,8 @@ impl<'a> Default for VimMapper {
             nodes: HashMap::with_capacity(50),
             //Account for the already-added root node
             node_idx_count: 1,
+            edges: HashMap::new(),
+            edge_idx_count: 0,
             translate: DEFAULT_TRANSLATE,
             scale: DEFAULT_SCALE,
             offset_x: DEFAULT_OFFSET_X,
@@ -161,6 +205,7 @@ impl<'a> Default for VimMapper {
             debug_data: false,
             debug_visuals: false,
             largest_node_movement: None,
+            simulation_time: 0.,
             canvas_rect: None,
             config,
             node_render_mode: NodeRenderMode::AllEnabled,
@@ -168,6 +213,15 @@ impl<'a> Default for VimMapper {
             last_traverse_angle: TAU-FRAC_PI_2,
             enabled_layouts: HashMap::new(),
             disabled_layouts: HashMap::new(),
+            edge_layouts: HashMap::new(),
+            edited_edge: None,
+            edited_note: None,
+            folds_stale: false,
+            layout_mode: LayoutMode::Force,
+            minimap_shown: false,
+            minimap: None,
+            minimap_dragging: false,
+            spatial_index: VMSpatialIndex::new(DEFAULT_SPATIAL_INDEX_CELL_SIZE),
             root_nodes: HashMap::new(),
             input_manager: VMInputManager::new(),
             last_mouse_down_data: None,
@@ -176,6 +230,9 @@ impl<'a> Default for VimMapper {
             zoom_level_index: DEFAULT_ZOOM_INDEX,
             zoomed_while_right_click: false,
             search_string: String::from(""),
+            search_error: None,
+            search_matches: HashMap::new(),
+            search_layouts: HashMap::new(),
         };
         let root_fg_index = root_node.fg_index.unwrap();
         mapper.nodes.insert(0, root_node);
@@ -188,6 +245,7 @@ impl<'a> Default for VimMapper {
 impl<'a> VimMapper {
     pub fn new(config: VMConfigVersion4) -> VimMapper {
         let mut vm = VimMapper::default();
+        vm.graph.parameters.seed = config.get_layout_seed();
         vm.config = config;
         vm
     }
@@ -262,6 +320,10 @@ impl<'a> VimMapper {
         for node_fg_idx in self.graph.get_graph().neighbors(
             node.fg_index.expect("Tried to get a non-existent fg_index from a node"))
         {
+            //Nodes hidden by a fold can't be targeted
+            if self.graph.get_graph()[node_fg_idx].data.is_hidden {
+                continue;
+            }
             let new_target_node_idx = self.graph.get_graph()[node_fg_idx].data.user_data;
 
             let target_node = self.nodes.get(&new_target_node_idx).unwrap();
@@ -303,26 +365,66 @@ impl<'a> VimMapper {
     pub fn build_target_list_from_string(&mut self, search_string: String) -> Result<(), ()> {
         self.target_node_idx = None;
         self.target_node_list.clear();
-        let regex_string = format!("(?i){}", search_string);
-        let regex = Regex::new(&regex_string).expect("Failed to compile search regex");
-        for (idx, node) in &self.nodes {
-            if regex.is_match(&node.get_label()) {
-                if Some(*idx) != self.get_active_node_idx() {
-                    self.target_node_list.push(*idx);
-                }
+        //Pull #tag and key:value filters out of a fuzzy search string. Whatever is left is matched against
+        // the node labels. Regex and literal searches match everything after their prefix as one pattern.
+        let mut tag_filters: Vec<&str> = vec![];
+        let mut property_filters: Vec<(String, String)> = vec![];
+        let mut label_terms: Vec<&str> = vec![];
+        let property_regex = Regex::new(r"^(\w+):(.*)$").expect("Failed to compile property filter regex");
+        let fuzzy = is_fuzzy_search(&search_string);
+        //Terms are split the way node metadata is entered, so quoted property values can contain spaces
+        let terms = if fuzzy {
+            VMNode::split_metadata_tokens(&search_string)
+        } else {
+            vec![search_string.clone()]
+        };
+        for term in &terms {
+            if !fuzzy {
+                label_terms.push(term);
+            } else if let Some(tag) = term.strip_prefix('#') {
+                tag_filters.push(tag);
+            } else if let Some(captures) = property_regex.captures(term) {
+                property_filters.push((captures[1].to_string(), captures[2].trim_matches('"').to_string()));
+            } else {
+                label_terms.push(term);
             }
         }
-        self.target_node_list.sort_unstable_by(|a1, a2| {
-            let node1 = self.nodes.get(a1).unwrap();
-            let node2 = self.nodes.get(a2).unwrap();
-            if node1.fg_index.unwrap() > node2.fg_index.unwrap() {
-                return std::cmp::Ordering::Greater;
-            } else if node1.fg_index.unwrap() < node2.fg_index.unwrap() {
-                return std::cmp::Ordering::Less;
-            } else {
-                return std::cmp::Ordering::Equal;
+        self.search_matches.clear();
+        self.search_layouts.clear();
+        let matcher = match SearchMatcher::new(&label_terms.join(" ")) {
+            Ok(matcher) => matcher,
+            Err(message) => {
+                self.search_error = Some(message);
+                return Err(());
             }
+        };
+        self.search_error = None;
+        let active_idx = self.get_active_node_idx();
+        //Results are ranked by score, with nodes matched by their label ahead of those only matched by their
+        // note. Ties are broken by the order the nodes were created in.
+        let mut results: Vec<(u32, bool, i64)> = vec![];
+        for (idx, node) in &self.nodes {
+            if Some(*idx) == active_idx
+            || !tag_filters.iter().all(|tag| node.has_tag_prefix(tag))
+            || !property_filters.iter().all(|(key, value)| node.has_property_match(key, value)) {
+                continue;
+            }
+            //Labels are matched as they're displayed, without their markdown, so that the matched
+            // characters can be highlighted in the results list
+            let (label, _) = parse_inline_markdown(&node.get_label());
+            if let Some(found) = matcher.find(&label) {
+                results.push((*idx, true, found.score));
+                self.search_matches.insert(*idx, found.ranges);
+            } else if let Some(found) = node.get_note().and_then(|note| matcher.find(&note)) {
+                results.push((*idx, false, found.score));
+            }
+        }
+        results.sort_unstable_by(|(idx1, in_label1, score1), (idx2, in_label2, score2)| {
+            return in_label2.cmp(in_label1)
+                .then(score2.cmp(score1))
+                .then(self.nodes[idx1].fg_index.unwrap().cmp(&self.nodes[idx2].fg_index.unwrap()));
         });
+        self.target_node_list = results.into_iter().map(|(idx, _, _)| idx).collect();
         if self.target_node_list.len() > 0 {
             self.target_node_idx = Some(0);
             return Ok(());
@@ -613,13 +715,12 @@ impl<'a> VimMapper {
         //Set animating to true to allow frozen sheets to adapt to new node
         self.animating = true;
         let new_node_idx = self.increment_node_idx();
-        // let new_edge_idx = self.increment_edge_idx();
         let from_node_pos = self.get_node_pos(from_idx);
         let from_node = self.nodes.get_mut(&from_idx);
 
         //Offset the new node from its progenitor to keep the ForceGraph from applying too-great repulsion
-        // forces.
-        let offset_vec = Vec2::new(rand::random::<f64>()-0.5, rand::random::<f64>()-0.5) * self.graph.parameters.min_attract_distance;
+        // forces. The offset is seeded when the sheet has a layout seed.
+        let offset_vec = Vec2::from(self.graph.random_offset()) * self.graph.parameters.min_attract_distance;
         let new_node_pos = Vec2::new(from_node_pos.x + offset_vec.x, from_node_pos.y + offset_vec.y);
         match from_node {
             //Nodes must be added from an existing node.
@@ -643,8 +744,10 @@ impl<'a> VimMapper {
                     mass: DEFAULT_NODE_MASS,
                     ..Default::default()
                 }));
-                self.graph.add_edge(from_node.fg_index.unwrap(), new_node.fg_index.unwrap(), EdgeData { user_data: 0 }); 
+                let from_fg_index = from_node.fg_index.unwrap();
+                let new_fg_index = new_node.fg_index.unwrap();
                 self.nodes.insert(new_node.index, new_node);
+                self.add_edge(from_fg_index, new_fg_index);
             }
             _ => {
                 panic!("Tried to add to a non-existent node")
@@ -697,11 +800,21 @@ impl<'a> VimMapper {
                     mass: DEFAULT_NODE_MASS,
                     ..Default::default()
                 }));
-                self.graph.add_edge(from_node.unwrap(), new_vmnode.fg_index.unwrap(), EdgeData { user_data: 0 }); 
-                self.graph.add_edge(to_node.unwrap(), new_vmnode.fg_index.unwrap(), EdgeData { user_data: 0 }); 
+                let new_fg_index = new_vmnode.fg_index.unwrap();
                 self.nodes.insert(new_vmnode.index, new_vmnode);
-                let old_edge = self.graph.get_graph().find_edge(from_node.unwrap(), to_node.unwrap());
-                self.graph.get_graph_mut().remove_edge(old_edge.unwrap());
+                //Keep the old edge's data (label, etc.) on the from side of the inserted node
+                let old_edge = self.graph.get_graph().find_edge(from_node.unwrap(), to_node.unwrap()).unwrap();
+                let (old_source, _) = self.graph.get_graph().edge_endpoints(old_edge).unwrap();
+                let old_edge_data = self.graph.get_graph_mut().remove_edge(old_edge).unwrap();
+                //Express the old direction relative to from -> to so both halves point the same way
+                let mut direction = self.edges[&old_edge_data.user_data].get_direction();
+                if old_source != from_node.unwrap() {
+                    direction = direction.reversed();
+                    self.edges.get_mut(&old_edge_data.user_data).unwrap().set_direction(direction);
+                }
+                self.graph.add_edge(from_node.unwrap(), new_fg_index, old_edge_data);
+                let new_edge_idx = self.add_edge(new_fg_index, to_node.unwrap());
+                self.edges.get_mut(&new_edge_idx).unwrap().set_direction(direction);
                 self.animating = true;
                 self.set_node_as_active(new_node_idx);
                 return Some(new_node_idx);
@@ -739,18 +852,21 @@ impl<'a> VimMapper {
                 return Err(String::from("Node has more than 2 neighbors"));
             } else if neighbor_count == 2 {
                 VMGraphClip::dispatch(ctx, &self, &hash_set!{node.fg_index.unwrap()}, node.fg_index.unwrap(), &"0".to_string());
-                self.graph.add_edge(neighbors[0], neighbors[1], EdgeData { user_data: 0 });
-                self.graph.remove_node(node.fg_index.unwrap());
+                let fg_index = node.fg_index.unwrap();
+                self.add_edge(neighbors[0], neighbors[1]);
+                self.remove_graph_node(fg_index);
                 self.nodes.remove(&idx);
                 self.animating = true;
                 return Ok(self.graph.get_graph()[neighbors[0]].data.user_data)
             } else if neighbor_count == 1{
-                self.graph.remove_node(node.fg_index.unwrap());
+                let fg_index = node.fg_index.unwrap();
+                self.remove_graph_node(fg_index);
                 self.nodes.remove(&idx);
                 self.animating = true;
                 return Ok(self.graph.get_graph()[neighbors[0]].data.user_data);
             } else {
-                self.graph.remove_node(node.fg_index.unwrap());
+                let fg_index = node.fg_index.unwrap();
+                self.remove_graph_node(fg_index);
                 self.nodes.remove(&idx);
                 self.animating = true;
                 return Ok(0);
@@ -770,6 +886,87 @@ impl<'a> VimMapper {
         self.root_nodes = new_roots;
     }
 
+    //Joins two existing nodes with a new edge. Linking two separate components merges them, so only one
+    // of their roots is kept: the main root if it's one of them, otherwise the root of from_idx. The other
+    // stays anchored but loses its numbered mark. If neither side has a root, one is assigned as it is for
+    // a component split off by unlink_nodes.
+    pub fn link_nodes(&mut self, from_idx: u32, to_idx: u32) -> Result<u32, String> {
+        if from_idx == to_idx {
+            return Err(String::from("Cannot link a node to itself."));
+        }
+        let (from_fg, to_fg) = match (self.nodes.get(&from_idx), self.nodes.get(&to_idx)) {
+            (Some(from_node), Some(to_node)) => (from_node.fg_index.unwrap(), to_node.fg_index.unwrap()),
+            _ => return Err(String::from("Node not found")),
+        };
+        if self.graph.get_graph().find_edge(from_fg, to_fg).is_some() {
+            return Err(String::from("Nodes are already linked."));
+        }
+        let mut needs_root = false;
+        if !self.graph.are_nodes_connected(from_fg, to_fg) {
+            let from_root = self.root_nodes.get(&self.graph.get_node_component(from_fg)).cloned();
+            let to_root = self.root_nodes.get(&self.graph.get_node_component(to_fg)).cloned();
+            match (from_root, to_root) {
+                (Some(from_root), Some(to_root)) => {
+                    let demoted_root = if self.graph.get_graph()[to_root].data.user_data == 0 { from_root } else { to_root };
+                    self.root_nodes.retain(|_, v| *v != demoted_root);
+                    self.nodes.get_mut(&self.graph.get_graph()[demoted_root].data.user_data).unwrap().mark = None;
+                },
+                //A side without a root joins the tree of the other side
+                (Some(_), None) | (None, Some(_)) => (),
+                (None, None) => needs_root = true,
+            }
+        }
+        let edge_idx = self.add_edge(from_fg, to_fg);
+        self.rebuild_root_nodes();
+        if needs_root {
+            self.assign_component_root(from_fg);
+        }
+        self.animating = true;
+        return Ok(edge_idx);
+    }
+
+    //Removes the edge joining two nodes without removing either node. If that splits a component, the
+    // half left without a root is given one: its lowest-indexed anchored node if it has any, otherwise
+    // the unlinked node on that side, which is anchored so that every component keeps an anchor.
+    pub fn unlink_nodes(&mut self, idx1: u32, idx2: u32) -> Result<(), String> {
+        let edge_idx = self.get_edge_between(idx1, idx2).ok_or(String::from("Nodes are not linked."))?;
+        let fg1 = self.nodes[&idx1].fg_index.unwrap();
+        let fg2 = self.nodes[&idx2].fg_index.unwrap();
+        let edge = self.graph.get_graph().find_edge(fg1, fg2).unwrap();
+        self.graph.get_graph_mut().remove_edge(edge);
+        self.edges.remove(&edge_idx);
+        self.edge_layouts.remove(&edge_idx);
+        self.animating = true;
+        if self.graph.are_nodes_connected(fg1, fg2) {
+            return Ok(());
+        }
+        self.rebuild_root_nodes();
+        let orphan = if self.root_nodes.contains_key(&self.graph.get_node_component(fg1)) { fg2 } else { fg1 };
+        self.assign_component_root(orphan);
+        return Ok(());
+    }
+
+    //Gives the component containing a node a root: its lowest-indexed anchored node if it has any,
+    // otherwise the node itself, which is anchored so that every component keeps an anchor.
+    fn assign_component_root(&mut self, fg_idx: DefaultNodeIdx) {
+        let mut new_root = fg_idx;
+        let mut lowest_anchor: Option<u32> = None;
+        let mut bfs = Bfs::new(self.graph.get_graph(), fg_idx);
+        while let Some(visited) = bfs.next(self.graph.get_graph()) {
+            let data = &self.graph.get_graph()[visited].data;
+            if data.is_anchor && lowest_anchor.map_or(true, |lowest| data.user_data < lowest) {
+                lowest_anchor = Some(data.user_data);
+                new_root = visited;
+            }
+        }
+        if lowest_anchor.is_none() {
+            self.graph.get_graph_mut()[fg_idx].toggle_anchor();
+        }
+        let component = self.graph.get_node_component(new_root);
+        self.root_nodes.insert(component, new_root);
+        self.rebuild_root_nodes();
+    }
+
     //Deletes a leaf node. Returns the global index of the node it was attached to. Currently only
     // nodes with a single edge (leaf nodes) can be deleted.
     // TODO: implement graph traversal to allow any node (save the root) to be deleted along with
@@ -812,7 +1009,7 @@ impl<'a> VimMapper {
                         self.root_nodes.remove(&node_component);
                     }
                     self.nodes.remove(&self.graph.get_graph()[fg_idx].data.user_data);
-                    self.graph.remove_node(fg_idx);
+                    self.remove_graph_node(fg_idx);
                     self.enabled_layouts.remove(&fg_idx);
                     self.disabled_layouts.remove(&fg_idx);
                 }
@@ -830,7 +1027,7 @@ impl<'a> VimMapper {
                 } else {
                     for fg_idx in removal_list {
                         self.nodes.remove(&self.graph.get_graph()[fg_idx].data.user_data);
-                        self.graph.remove_node(fg_idx);
+                        self.remove_graph_node(fg_idx);
                         self.enabled_layouts.remove(&fg_idx);
                         self.disabled_layouts.remove(&fg_idx);
                     }
@@ -867,6 +1064,7 @@ impl<'a> VimMapper {
     //Iterate through the node HashMap to set the active node. All nodes except the specified are marked
     // as inactive in the process.
     pub fn set_node_as_active(&mut self, idx: u32) {
+        self.unfold_to_node(idx);
         if let Some(node) = self.get_active_node_idx() {
             let node_pos = self.get_node_pos(node);
             let target_node_pos = self.get_node_pos(idx);
@@ -938,17 +1136,420 @@ impl<'a> VimMapper {
         idx
     }
 
+    //Return the current edge count and increment.
+    pub fn increment_edge_idx(&mut self) -> u32 {
+        let idx = self.edge_idx_count.clone();
+        self.edge_idx_count += 1;
+        idx
+    }
+
+    //Adds an edge between two ForceGraph nodes along with its VMEdge. Returns the global index of the
+    // edge. If the nodes are already joined, the existing edge's index is returned instead.
+    pub fn add_edge(&mut self, n1: DefaultNodeIdx, n2: DefaultNodeIdx) -> u32 {
+        if let Some(edge) = self.graph.get_graph().find_edge(n1, n2) {
+            return self.graph.get_graph()[edge].user_data;
+        }
+        let idx = self.increment_edge_idx();
+        self.edges.insert(idx, VMEdge::with_fields(None, EdgeDirection::None, idx));
+        self.graph.add_edge(n1, n2, EdgeData { user_data: idx, ..Default::default() });
+        self.folds_stale = true;
+        idx
+    }
+
+    //Removes a node from the ForceGraph along with the VMEdges of all edges that touch it.
+    pub fn remove_graph_node(&mut self, fg_idx: DefaultNodeIdx) {
+        let edge_indices = self.graph.get_graph().edges(fg_idx).map(|e| e.weight().user_data).collect::<Vec<u32>>();
+        for edge_idx in edge_indices {
+            self.edges.remove(&edge_idx);
+            self.edge_layouts.remove(&edge_idx);
+        }
+        self.graph.remove_node(fg_idx);
+        self.folds_stale = true;
+    }
+
+    //Returns the global index of the edge joining two nodes, if any.
+    pub fn get_edge_between(&self, idx1: u32, idx2: u32) -> Option<u32> {
+        let n1 = self.nodes.get(&idx1)?.fg_index?;
+        let n2 = self.nodes.get(&idx2)?.fg_index?;
+        let edge = self.graph.get_graph().find_edge(n1, n2)?;
+        return Some(self.graph.get_graph()[edge].user_data);
+    }
+
+    //Toggles the arrowhead at the to_idx end of the edge joining two nodes.
+    pub fn toggle_edge_arrow(&mut self, from_idx: u32, to_idx: u32) {
+        if let Some(edge_idx) = self.get_edge_between(from_idx, to_idx) {
+            let from_fg = self.nodes[&from_idx].fg_index.unwrap();
+            let to_fg = self.nodes[&to_idx].fg_index.unwrap();
+            let fg_edge = self.graph.get_graph().find_edge(from_fg, to_fg).unwrap();
+            let (source, _) = self.graph.get_graph().edge_endpoints(fg_edge).unwrap();
+            let from_is_source = source == from_fg;
+            let edge = self.edges.get_mut(&edge_idx).unwrap();
+            if from_is_source {
+                edge.set_direction(edge.get_direction().toggle_target());
+            } else {
+                edge.set_direction(edge.get_direction().toggle_source());
+            }
+        }
+    }
+
+    //Adds a child of the active node that refers to a node in another tab
+    pub fn add_reference_node(&mut self, reference: VMNodeReference, label: String) -> Option<u32> {
+        if let Some(active_idx) = self.get_active_node_idx() {
+            if let Some(new_idx) = self.add_node(active_idx, label) {
+                self.nodes.get_mut(&new_idx).unwrap().reference = Some(reference);
+                return Some(new_idx);
+            }
+        }
+        return None;
+    }
+
+    pub fn get_active_node_reference(&self) -> Option<VMNodeReference> {
+        if let Some(idx) = self.get_active_node_idx() {
+            return self.nodes[&idx].reference.clone();
+        } else {
+            return None;
+        }
+    }
+
+    pub fn set_active_node_reference_broken(&mut self, broken: bool) {
+        if let Some(idx) = self.get_active_node_idx() {
+            if let Some(reference) = &mut self.nodes.get_mut(&idx).unwrap().reference {
+                reference.broken = broken;
+            }
+        }
+    }
+
+    //Points references to a renamed tab at its new name
+    pub fn rename_references(&mut self, old_tab_name: &str, new_tab_name: &str) {
+        for (_, node) in &mut self.nodes {
+            if let Some(reference) = &mut node.reference {
+                if reference.tab_name == old_tab_name {
+                    reference.tab_name = new_tab_name.to_string();
+                }
+            }
+        }
+    }
+
+    //Flags references to a deleted tab so they're drawn as broken
+    pub fn break_references(&mut self, tab_name: &str) {
+        for (_, node) in &mut self.nodes {
+            if let Some(reference) = &mut node.reference {
+                if reference.tab_name == tab_name {
+                    reference.broken = true;
+                }
+            }
+        }
+    }
+
+    //Activates a node and brings it into view.
+    pub fn reveal_node(&mut self, idx: u32) {
+        self.set_node_as_active(idx);
+        self.build_target_list_from_neighbors(idx);
+        self.bring_node_into_view(idx);
+    }
+
+    //Scrolls a node into view, leaving the viewport alone if it's already visible. Nodes that haven't been
+    // laid out yet (in a tab that hasn't been shown or just unfolded) have no rect to scroll to, so they're
+    // centered instead.
+    pub fn bring_node_into_view(&mut self, idx: u32) {
+        let has_rect = self.nodes[&idx].fg_index.and_then(|fg_index| self.spatial_index.get_rect(fg_index)).is_some()
+            || !self.nodes[&idx].node_rect.is_empty();
+        if !has_rect {
+            let node_pos = self.get_node_pos(idx) * self.scale.as_tuple().1;
+            self.offset_x = -1. * node_pos.x;
+            self.offset_y = -1. * node_pos.y;
+        } else {
+            self.scroll_node_into_view(idx);
+        }
+    }
+
+    //Returns the descendants of a node that folding it would hide. The node itself isn't included.
+    fn get_fold_descendants(&mut self, idx: u32) -> HashSet<DefaultNodeIdx> {
+        let fg_index = self.nodes[&idx].fg_index.unwrap();
+        let node_component = self.graph.get_node_component(fg_index);
+        if let Some(component_root) = self.root_nodes.get(&node_component).cloned() {
+            let (mut descendants, _) = self.graph.get_node_descendant_tree(fg_index, component_root);
+            descendants.remove(&fg_index);
+            return descendants;
+        } else {
+            return HashSet::new();
+        }
+    }
+
+    //Recomputes which nodes are hidden by folded nodes. Hidden nodes are left out of the simulation and
+    // aren't painted or targeted.
+    pub fn refresh_folds(&mut self) {
+        self.folds_stale = false;
+        let folded = self.nodes.iter().filter(|(_, node)| node.folded).map(|(idx, _)| *idx).collect::<Vec<u32>>();
+        let mut hidden: HashSet<DefaultNodeIdx> = HashSet::new();
+        for idx in folded {
+            let descendants = self.get_fold_descendants(idx);
+            self.nodes.get_mut(&idx).unwrap().hidden_count = descendants.len();
+            hidden.extend(descendants);
+        }
+        self.graph.visit_nodes_mut(|fg_node| {
+            fg_node.data.is_hidden = hidden.contains(&fg_node.index());
+        });
+        //Hidden nodes aren't painted, so their cached rects would go stale
+        for node in self.nodes.values_mut() {
+            if hidden.contains(&node.fg_index.unwrap()) {
+                node.node_rect = Rect::new(0.,0.,0.,0.);
+            }
+        }
+    }
+
+    //Folds a node, hiding its descendants. Nodes without descendants can't be folded.
+    pub fn fold_node(&mut self, idx: u32) {
+        if !self.nodes[&idx].folded && !self.get_fold_descendants(idx).is_empty() {
+            self.nodes.get_mut(&idx).unwrap().folded = true;
+            self.refresh_folds();
+            self.animating = true;
+        }
+    }
+
+    pub fn unfold_node(&mut self, idx: u32) {
+        if self.nodes[&idx].folded {
+            self.nodes.get_mut(&idx).unwrap().folded = false;
+            self.refresh_folds();
+            self.animating = true;
+        }
+    }
+
+    pub fn toggle_node_fold(&mut self, idx: u32) {
+        if self.nodes[&idx].folded {
+            self.unfold_node(idx);
+        } else {
+            self.fold_node(idx);
+        }
+    }
+
+    //Unfolds every folded node hiding the given node so that it can be shown.
+    pub fn unfold_to_node(&mut self, idx: u32) {
+        if self.folds_stale {
+            self.refresh_folds();
+        }
+        let fg_index = if let Some(fg_index) = self.nodes.get(&idx).and_then(|node| node.fg_index) {
+            fg_index
+        } else {
+            return;
+        };
+        if !self.graph.get_graph()[fg_index].data.is_hidden {
+            return;
+        }
+        let folded = self.nodes.iter().filter(|(_, node)| node.folded).map(|(idx, _)| *idx).collect::<Vec<u32>>();
+        for folded_idx in folded {
+            if self.get_fold_descendants(folded_idx).contains(&fg_index) {
+                self.nodes.get_mut(&folded_idx).unwrap().folded = false;
+            }
+        }
+        self.refresh_folds();
+        self.animating = true;
+    }
+
+    pub fn get_active_node_link(&self) -> Option<String> {
+        if let Some(idx) = self.get_active_node_idx() {
+            return self.nodes[&idx].link.clone();
+        } else {
+            return None;
+        }
+    }
+
+    pub fn get_active_node_metadata_string(&self) -> String {
+        if let Some(idx) = self.get_active_node_idx() {
+            return self.nodes[&idx].get_metadata_string();
+        } else {
+            return String::new();
+        }
+    }
+
     pub fn invalidate_node_layouts(&mut self) {
         self.nodes.iter_mut().for_each(|(_, node)| {
             self.enabled_layouts.remove(&node.fg_index.unwrap());
             self.disabled_layouts.remove(&node.fg_index.unwrap());
             node.node_rect = Rect::new(0.,0.,0.,0.);
         });
+        self.edge_layouts.clear();
+        self.search_layouts.clear();
+    }
+
+    //Applies the named style bound to the given key on top of a node's current style. Space clears all
+    // of the node's style overrides.
+    pub fn apply_node_style(&mut self, idx: u32, key: String) {
+        let named_style = self.config.get_node_styles().into_iter().find(|named| named.key == key);
+        if let Some(node) = self.nodes.get_mut(&idx) {
+            if key == " " {
+                node.style = VMNodeStyle::default();
+            } else if let Some(named_style) = named_style {
+                node.style.merge(&named_style.style);
+            } else {
+                return;
+            }
+            let fg_index = node.fg_index.unwrap();
+            self.invalidate_node_layout(fg_index);
+        }
+    }
+
+    //Paints the named styles from the config while the style picker is open. Each entry is drawn in its
+    // own style so the result can be previewed before it's applied.
+    fn paint_style_picker(&self, ctx: &mut PaintCtx) {
+        let mut entries: Vec<(String, VMNodeStyle)> = self.config.get_node_styles().into_iter()
+            .map(|named| (format!("{}  {}", named.key, named.name), named.style))
+            .collect();
+        entries.push((String::from("Space  clear"), VMNodeStyle::default()));
+        let mut rows: Vec<(PietTextLayout, VMNodeStyle)> = vec![];
+        let mut max_width: f64 = 0.;
+        for (text, style) in entries {
+            let layout = ctx.text().new_text_layout(text)
+            .font(FontFamily::SANS_SERIF, DEFAULT_SEARCH_TERM_FONT_SIZE)
+            .default_attribute(style.get_font_weight())
+            .text_color(style.get_text_color().unwrap_or(self.config.get_color(VMColor::LabelTextColor).expect("Couldn't get LabelTextColor")))
+            .build().unwrap();
+            max_width = max_width.max(layout.size().width);
+            rows.push((layout, style));
+        }
+        let x = DEFAULT_STACK_X_MARGIN;
+        let mut y = DEFAULT_STACK_Y_TOP_MARGIN + TAB_BAR_HEIGHT;
+        let pane_height = rows.iter().map(|(layout, _)| layout.size().height + DEFAULT_STACK_SPACING).sum::<f64>() + 2.*DEFAULT_STACK_PADDING;
+        let pane_rect = Rect::new(x, y, x + max_width + 2.*(DEFAULT_STACK_PADDING + DEFAULT_BORDER_WIDTH), y + pane_height);
+        ctx.fill(pane_rect, &self.config.get_color(VMColor::StackBackgroundColor).unwrap());
+        ctx.stroke(
+            pane_rect.to_rounded_rect(DEFAULT_BORDER_RADIUS),
+            &self.config.get_color(VMColor::NodeBorderColor).expect("Could not get NodeBorderColor from config"),
+            DEFAULT_BORDER_WIDTH);
+        y += DEFAULT_STACK_PADDING + DEFAULT_STACK_SPACING/2.;
+        for (layout, style) in rows {
+            let rect = Rect::from_origin_size(Point::new(x + DEFAULT_STACK_PADDING + DEFAULT_BORDER_WIDTH, y), layout.size());
+            let border = rect.inflate(DEFAULT_BORDER_WIDTH, DEFAULT_BORDER_WIDTH);
+            let border = border.to_rounded_rect(style.get_border_radius(&border));
+            ctx.fill(border, &style.get_fill_color().unwrap_or(self.config.get_color(VMColor::NodeBackgroundColor).unwrap()));
+            ctx.stroke(border, &style.get_border_color().unwrap_or(self.config.get_color(VMColor::NodeBorderColor).unwrap()), DEFAULT_MARK_BORDER_WIDTH);
+            ctx.draw_text(&layout, rect.origin());
+            y += layout.size().height + DEFAULT_STACK_SPACING;
+        }
+    }
+
+    //Paints the note being edited in a panel along the right side of the sheet, headed by the label of
+    // the node it belongs to.
+    fn paint_note_panel(&mut self, ctx: &mut PaintCtx) {
+        let node = self.nodes.get_mut(&self.edited_note.unwrap()).unwrap();
+        let header_text = ctx.text().new_text_layout(format!("Notes — {}", node.get_label()))
+        .font(FontFamily::SANS_SERIF, DEFAULT_SEARCH_TERM_FONT_SIZE)
+        .default_attribute(FontWeight::BOLD)
+        .text_color(self.config.get_color(VMColor::LabelTextColor).expect("Couldn't get LabelTextColor"))
+        .max_width(DEFAULT_NOTE_PANEL_WIDTH - 2.*DEFAULT_STACK_PADDING)
+        .build().unwrap();
+        let note_height = node.note_input.text_layout.as_ref().map(|layout| layout.size().height).unwrap_or(0.);
+        let ctx_size = ctx.size();
+        let x = ctx_size.width - DEFAULT_NOTE_PANEL_WIDTH - DEFAULT_STACK_X_MARGIN;
+        let y = DEFAULT_STACK_Y_TOP_MARGIN + TAB_BAR_HEIGHT;
+        let pane_rect = Rect::new(x, y, x + DEFAULT_NOTE_PANEL_WIDTH,
+            (y + header_text.size().height + note_height + DEFAULT_STACK_SPACING + 2.*DEFAULT_STACK_PADDING)
+                .max(y + DEFAULT_NOTE_PANEL_MIN_HEIGHT)
+                .min(ctx_size.height - DEFAULT_STACK_Y_BOTTOM_MARGIN)
+        );
+        ctx.with_save(|ctx| {
+            ctx.fill(pane_rect, &self.config.get_color(VMColor::StackBackgroundColor).unwrap());
+            ctx.stroke(
+                pane_rect.to_rounded_rect(DEFAULT_BORDER_RADIUS),
+                &self.config.get_color(VMColor::ActiveNodeBorderColor).expect("Could not get ActiveNodeBorderColor from config"),
+                DEFAULT_BORDER_WIDTH);
+            ctx.clip(pane_rect.inflate(-2., -2.));
+            ctx.draw_text(&header_text, Point::new(x + DEFAULT_STACK_PADDING, y + DEFAULT_STACK_PADDING));
+            ctx.transform(Affine::translate(Vec2::new(
+                x + DEFAULT_STACK_PADDING,
+                y + DEFAULT_STACK_PADDING + header_text.size().height + DEFAULT_STACK_SPACING)));
+            node.note_input.paint(ctx, &self.config, self.debug_data);
+        });
+    }
+
+    //Paints an overview of the whole sheet in the configured corner. Nodes are drawn as dots and edges as
+    // thin lines, scaled to fit, with the part of the sheet that's on screen outlined.
+    fn paint_minimap(&mut self, ctx: &mut PaintCtx) {
+        let ctx_size = ctx.size();
+        let (width, height) = self.config.get_minimap_size();
+        let corner = self.config.get_minimap_corner();
+        let x = match corner {
+            MinimapCorner::TopLeft | MinimapCorner::BottomLeft => DEFAULT_STACK_X_MARGIN,
+            MinimapCorner::TopRight | MinimapCorner::BottomRight => ctx_size.width - width - DEFAULT_STACK_X_MARGIN,
+        };
+        let y = match corner {
+            MinimapCorner::TopLeft | MinimapCorner::TopRight => DEFAULT_STACK_Y_TOP_MARGIN + TAB_BAR_HEIGHT,
+            MinimapCorner::BottomLeft | MinimapCorner::BottomRight => ctx_size.height - height - DEFAULT_STACK_Y_BOTTOM_MARGIN,
+        };
+        let pane_rect = Rect::new(x, y, x + width, y + height);
+
+        let mut bounds: Option<Rect> = None;
+        self.graph.visit_nodes(|node| {
+            if node.data.is_hidden {
+                return;
+            }
+            let point = Point::new(node.x() as f64, node.y() as f64);
+            bounds = Some(bounds.map_or(Rect::from_points(point, point), |rect| rect.union_pt(point)));
+        });
+        let bounds = bounds.unwrap_or_default();
+        //Fit the nodes inside the minimap. Small sheets are shown at their actual size rather than magnified.
+        let inner_rect = pane_rect.inset(-DEFAULT_MINIMAP_PADDING);
+        let scale = (inner_rect.width() / bounds.width().max(1.))
+            .min(inner_rect.height() / bounds.height().max(1.))
+            .min(1.);
+        let transform = TranslateScale::new(inner_rect.center().to_vec2() - bounds.center().to_vec2() * scale, scale);
+        let screen_to_canvas = Affine::from(self.scale).inverse() * Affine::from(self.translate).inverse();
+        let viewport = Rect::from_points(
+            screen_to_canvas * Point::ZERO,
+            screen_to_canvas * Point::new(ctx_size.width, ctx_size.height));
+
+        let edge_color = self.config.get_color(VMColor::EdgeColor).expect("Could not get EdgeColor from config");
+        let node_color = self.config.get_color(VMColor::NodeBorderColor).expect("Could not get NodeBorderColor from config");
+        let active_color = self.config.get_color(VMColor::ActiveNodeBorderColor).expect("Could not get ActiveNodeBorderColor from config");
+        let active_idx = self.get_active_node_idx();
+        ctx.with_save(|ctx| {
+            ctx.fill(pane_rect, &self.config.get_color(VMColor::StackBackgroundColor).unwrap());
+            ctx.stroke(pane_rect.to_rounded_rect(DEFAULT_BORDER_RADIUS), &node_color, DEFAULT_BORDER_WIDTH);
+            ctx.clip(pane_rect.inflate(-2., -2.));
+            self.graph.visit_edges(|node1, node2, _edge| {
+                if node1.data.is_hidden || node2.data.is_hidden {
+                    return;
+                }
+                let p0 = transform * Point::new(node1.x() as f64, node1.y() as f64);
+                let p1 = transform * Point::new(node2.x() as f64, node2.y() as f64);
+                ctx.stroke(Line::new(p0, p1), &edge_color, 1.);
+            });
+            self.graph.visit_nodes(|node| {
+                if node.data.is_hidden {
+                    return;
+                }
+                let center = transform * Point::new(node.x() as f64, node.y() as f64);
+                let color = if Some(node.data.user_data) == active_idx { &active_color } else { &node_color };
+                ctx.fill(Circle::new(center, DEFAULT_MINIMAP_NODE_RADIUS), color);
+            });
+            ctx.stroke(transform * viewport, &active_color, 1.5);
+        });
+        self.minimap = Some((pane_rect, transform));
+    }
+
+    //Pans the sheet so that the point of the sheet under pos in the minimap is centered on screen.
+    fn pan_to_minimap_point(&mut self, pos: Point) {
+        if let Some((_, transform)) = self.minimap {
+            let canvas_pos = transform.inverse() * pos;
+            let scale = self.scale.as_tuple().1;
+            self.offset_x = -1. * canvas_pos.x * scale;
+            self.offset_y = -1. * canvas_pos.y * scale;
+        }
+    }
+
+    //Shows or hides the minimap of this tab.
+    pub fn toggle_minimap(&mut self) {
+        self.minimap_shown = !self.minimap_shown;
+        if !self.minimap_shown {
+            self.minimap = None;
+            self.minimap_dragging = false;
+        }
     }
 
     pub fn invalidate_node_layout(&mut self, idx: DefaultNodeIdx) {
         self.enabled_layouts.remove(&idx);
         self.disabled_layouts.remove(&idx);
+        self.search_layouts.remove(&self.graph.get_graph()[idx].data.user_data);
         self.nodes.get_mut(&self.graph.get_graph()[idx].data.user_data).unwrap().node_rect = Rect::new(0.,0.,0.,0.);
     }
 
@@ -1003,10 +1604,146 @@ impl<'a> VimMapper {
         }
     }
 
+    //Returns the ForceGraph data of the edge joining two nodes.
+    fn get_edge_data_between(&mut self, idx1: u32, idx2: u32) -> Option<&mut EdgeData<u32>> {
+        let n1 = self.nodes.get(&idx1)?.fg_index?;
+        let n2 = self.nodes.get(&idx2)?.fg_index?;
+        let edge = self.graph.get_graph().find_edge(n1, n2)?;
+        return Some(&mut self.graph.get_graph_mut()[edge]);
+    }
+
+    //Changes the rest length of the edge joining two nodes by the given amount. Edges without their own
+    // length start from the sheet's spring length. Lengths can't go below zero.
+    pub fn change_edge_length(&mut self, idx1: u32, idx2: u32, amount: f64) {
+        let default_length = self.graph.parameters.spring_length;
+        if let Some(edge) = self.get_edge_data_between(idx1, idx2) {
+            let length = (edge.length.unwrap_or(default_length) + amount).max(0.);
+            if length == default_length {
+                edge.length = None;
+            } else {
+                edge.length = Some(length);
+            }
+            self.animating = true;
+        }
+    }
+
+    pub fn reset_edge_length(&mut self, idx1: u32, idx2: u32) {
+        if let Some(edge) = self.get_edge_data_between(idx1, idx2) {
+            edge.length = None;
+            self.animating = true;
+        }
+    }
+
+    //Formats the tab's simulation parameters as the name=value pairs accepted by
+    // set_simulation_parameters_from_string.
+    pub fn get_simulation_parameters_string(&self) -> String {
+        let parameters = &self.graph.parameters;
+        return format!(
+            "charge={} spring={} spring_length={} max_force={} speed={} damping={} min_attract={} padding={} gravity={} center_gravity={} theta={}",
+            parameters.force_charge,
+            parameters.force_spring,
+            parameters.spring_length,
+            parameters.force_max,
+            parameters.node_speed,
+            parameters.damping_factor,
+            parameters.min_attract_distance,
+            parameters.collision_padding,
+            parameters.component_gravity,
+            parameters.center_gravity,
+            parameters.barnes_hut_theta.map_or(String::from("none"), |theta| theta.to_string()),
+        );
+    }
+
+    //Applies space-separated name=value pairs to the tab's simulation parameters and restarts the
+    // simulation. Parameters that aren't named keep their values. Nothing is changed if any pair is
+    // invalid or out of its parameter's range, and the returned message describes the problem.
+    pub fn set_simulation_parameters_from_string(&mut self, text: &str) -> Result<(), String> {
+        let mut parameters = self.graph.parameters.clone();
+        for pair in text.split_whitespace() {
+            let (name, value) = pair.split_once('=').ok_or(format!("Expected name=value but found \"{}\"", pair))?;
+            if name == "theta" && value == "none" {
+                parameters.barnes_hut_theta = None;
+                continue;
+            }
+            let value = value.parse::<f64>().ok()
+                .filter(|value| value.is_finite())
+                .ok_or(format!("Invalid value for {}: \"{}\"", name, value))?;
+            let in_range = |valid: bool, range: &str| -> Result<f64, String> {
+                if valid {
+                    return Ok(value);
+                } else {
+                    return Err(format!("{} must be {} but was {}", name, range, value));
+                }
+            };
+            match name {
+                "charge" => parameters.force_charge = in_range(value >= 0., "at least 0")?,
+                "spring" => parameters.force_spring = in_range(value >= 0., "at least 0")?,
+                "spring_length" => parameters.spring_length = in_range(value >= 0., "at least 0")?,
+                "max_force" => parameters.force_max = in_range(value > 0., "greater than 0")?,
+                "speed" => parameters.node_speed = in_range(value > 0., "greater than 0")?,
+                "damping" => parameters.damping_factor = in_range(value > 0. && value <= 1., "greater than 0 and at most 1")?,
+                "min_attract" => parameters.min_attract_distance = in_range(value >= 0., "at least 0")?,
+                "padding" => parameters.collision_padding = in_range(value >= 0., "at least 0")?,
+                "gravity" => parameters.component_gravity = in_range(value >= 0., "at least 0")?,
+                "center_gravity" => parameters.center_gravity = in_range(value >= 0., "at least 0")?,
+                "theta" => parameters.barnes_hut_theta = Some(in_range(value >= 0., "at least 0 or none")?),
+                _ => return Err(format!("Unknown simulation parameter \"{}\"", name)),
+            }
+        }
+        self.graph.parameters = parameters;
+        self.restart_simulation();
+        return Ok(());
+    }
+
     pub fn restart_simulation(&mut self) {
         self.animating = true;
     }
 
+    //Spends the time since the last frame on fixed simulation steps, scaled by the configured simulation
+    // speed. Time left over is carried into the next frame. Returns the largest movement of any step, or
+    // None if no step was due this frame.
+    fn advance_simulation(&mut self, interval_nanos: u64) -> Option<f64> {
+        let elapsed = interval_nanos as f64 / 1_000_000_000. * self.config.get_simulation_speed();
+        self.simulation_time += elapsed.min(SIMULATION_STEP_INTERVAL * MAX_SIMULATION_STEPS_PER_FRAME as f64);
+        let mut largest_movement: Option<f64> = None;
+        let mut positions: Option<HashMap<DefaultNodeIdx, (f64, f64)>> = None;
+        while self.simulation_time >= SIMULATION_STEP_INTERVAL {
+            self.simulation_time -= SIMULATION_STEP_INTERVAL;
+            let movement = if self.layout_mode == LayoutMode::Force {
+                self.graph.update(DEFAULT_UPDATE_DELTA)
+            } else {
+                //The tree layout only depends on the graph's shape, so it's computed once per frame
+                if positions.is_none() {
+                    positions = Some(self.compute_layout_positions());
+                }
+                self.graph.approach_positions(positions.as_ref().unwrap(), DEFAULT_LAYOUT_EASING)
+            };
+            largest_movement = Some(largest_movement.map_or(movement, |largest| largest.max(movement)));
+        }
+        return largest_movement;
+    }
+
+    //Switches to the next layout mode. Switching back to the force layout resumes the simulation from
+    // wherever the tree layout left the nodes.
+    pub fn cycle_layout_mode(&mut self) {
+        self.layout_mode = self.layout_mode.next();
+        self.animating = true;
+    }
+
+    //Computes the positions nodes are moved toward while a tree layout is active. Node sizes are taken
+    // from their label layouts.
+    fn compute_layout_positions(&self) -> HashMap<DefaultNodeIdx, (f64, f64)> {
+        let mut roots = self.root_nodes.values().cloned().collect::<Vec<DefaultNodeIdx>>();
+        roots.sort();
+        return self.graph.compute_tree_layout(&roots, self.layout_mode, DEFAULT_TREE_LAYOUT_GAP, |fg_node| {
+            if let Some(layout) = self.enabled_layouts.get(&fg_node.index()) {
+                return (layout.size().width.max(DEFAULT_MIN_NODE_WIDTH_DATA), layout.size().height);
+            } else {
+                return (DEFAULT_MIN_NODE_WIDTH_DATA, DEFAULT_LABEL_FONT_SIZE);
+            }
+        });
+    }
+
     pub fn toggle_node_anchor(&mut self, idx: u32) {
         if let Some(node) = self.nodes.get_mut(&idx) {
             let fg_index = node.fg_index.unwrap();
@@ -1050,34 +1787,43 @@ impl<'a> VimMapper {
     }
 
     //Determine of a given Point (usually a click) intersects with a node. Return that node's index if so.
+    // Where nodes overlap, the one painted on top is returned: the active node, then the target, then the
+    // last node painted.
     pub fn does_point_collide(&mut self, point: Point) -> Option<u32> {
-        // self.last_click_point = Some(point);
-        let mut collided_index: Option<u32> = None;
-        self.nodes.iter().for_each(|item| {
-            let affine_scale = Affine::scale(self.scale.as_tuple().1);
-            let affine_translate = Affine::translate(self.translate.as_tuple().0);
-            let node = item.1;
-            let node_pos = &self.get_node_pos(node.index).clone();
-            let mut size = self.enabled_layouts[&node.fg_index.unwrap()].size();
-            if size.width < DEFAULT_MIN_NODE_WIDTH_DATA {
-                size.width = DEFAULT_MIN_NODE_WIDTH_DATA;
-            }
-            let mut rect = size.to_rect();
-            let border = DEFAULT_BORDER_WIDTH*self.scale.as_tuple().1;
-            rect = rect.inflate(border*2.0,border*2.0);
-            let pos_translate = Affine::translate(
-                (affine_scale * (
-                    *node_pos - Vec2::new(size.width/2.0, size.height/2.0)
-                ).to_point()).to_vec2()
-            );
-            rect = affine_scale.transform_rect_bbox(rect);
-            rect = (affine_translate).transform_rect_bbox(rect);
-            rect = (pos_translate).transform_rect_bbox(rect);
-            if rect.contains(point) {
-                collided_index = Some(node.index);
+        let canvas_point = Affine::from(self.scale).inverse() * (self.translate.inverse() * point);
+        let hits = self.spatial_index.query_point(canvas_point).into_iter()
+            .filter_map(|fg_index| self.graph.get_graph().node_weight(fg_index))
+            .filter(|fg_node| !fg_node.data.is_hidden)
+            .map(|fg_node| fg_node.data.user_data)
+            .collect::<Vec<u32>>();
+        for idx in [self.get_active_node_idx(), self.get_target_node_idx()].into_iter().flatten() {
+            if hits.contains(&idx) {
+                return Some(idx);
+            }
+        }
+        return hits.last().cloned();
+    }
+
+    //Rebuilds the spatial index from the current node positions and label sizes. Called on every layout,
+    // which follows each frame of simulation steps. Hidden nodes and nodes without a label layout yet are
+    // left out.
+    fn rebuild_spatial_index(&mut self) {
+        self.spatial_index.clear();
+        self.graph.visit_nodes(|fg_node| {
+            if fg_node.data.is_hidden {
+                return;
+            }
+            if let Some(layout) = self.enabled_layouts.get(&fg_node.index()) {
+                let mut size = layout.size();
+                if size.width < DEFAULT_MIN_NODE_WIDTH_DATA {
+                    size.width = DEFAULT_MIN_NODE_WIDTH_DATA;
+                }
+                //The painted box plus a border's width of slack so clicks on the border still land
+                let rect = Rect::from_center_size(Point::new(fg_node.x(), fg_node.y()), size)
+                    .inflate(DEFAULT_BORDER_WIDTH*2., DEFAULT_BORDER_WIDTH*2.);
+                self.spatial_index.insert(fg_node.index(), rect);
             }
         });
-        collided_index
     }
 
     pub fn screen_point_to_canvas_point(&self, point: Point) -> Point {
@@ -1123,20 +1869,22 @@ impl<'a> VimMapper {
     }
 
     //Loop over node label generation until it fits within a set of BoxConstraints. Wraps the contents
-    // once and then, if it still doesn't fit, reduce the font until it does.
-    pub fn build_label_layout_for_constraints(factory: &mut PietText, text: String, bc: BoxConstraints, color: &Color) -> Result<PietTextLayout, String> {
+    // once and then, if it still doesn't fit, reduce the font until it does. Any inline markdown spans are
+    // applied as text attributes.
+    pub fn build_label_layout_for_constraints(factory: &mut PietText, text: String, spans: &[InlineSpan], bc: BoxConstraints, color: &Color, style: &VMNodeStyle) -> Result<PietTextLayout, String> {
         let mut layout: PietTextLayout;
-        let mut font_size = DEFAULT_LABEL_FONT_SIZE;
+        let mut font_size = style.get_font_size();
         let max_width = NODE_LABEL_MAX_CONSTRAINTS.0;
 
         if bc.max().width < DEFAULT_MIN_NODE_WIDTH_DATA {
             return Err(format!("Cannot build label smaller than {}", DEFAULT_MIN_NODE_WIDTH_DATA));
         }
 
-        if let Ok(layout) = factory.new_text_layout(text.clone())
+        if let Ok(layout) = apply_inline_spans(factory.new_text_layout(text.clone())
         .font(FontFamily::SANS_SERIF, font_size)
+        .default_attribute(style.get_font_weight())
         .text_color((*color).clone())
-        .max_width(max_width)
+        .max_width(max_width), spans)
         .build() {
             if bc.contains(layout.size()) {
                 return Ok(layout);
@@ -1144,10 +1892,11 @@ impl<'a> VimMapper {
         }
 
         loop {
-            if let Ok(built) = factory.new_text_layout(text.clone()) 
+            if let Ok(built) = apply_inline_spans(factory.new_text_layout(text.clone()) 
             .font(FontFamily::SANS_SERIF, font_size)
+            .default_attribute(style.get_font_weight())
             .text_color(color.clone())
-            .max_width(max_width)
+            .max_width(max_width), spans)
             .build() {
                 layout = built;
             } else {
@@ -1185,9 +1934,14 @@ impl<'a> VimMapper {
         }
     }
 
+    //Nodes that are off screen aren't painted, so their cached screen rect can be out of date. The rect is
+    // taken from the spatial index instead when the node is in it.
     pub fn scroll_node_into_view(&mut self, idx: u32) {
         if let Some(node) = self.nodes.get(&idx) {
-            if !node.node_rect.is_empty() {
+            if let Some(rect) = node.fg_index.and_then(|fg_index| self.spatial_index.get_rect(fg_index)) {
+                let canvas_to_screen = Affine::from(self.translate) * Affine::from(self.scale);
+                self.scroll_rect_into_view(canvas_to_screen.transform_rect_bbox(rect));
+            } else if !node.node_rect.is_empty() {
                 self.scroll_rect_into_view(node.node_rect);
             }
         }
@@ -1218,6 +1972,12 @@ impl<'a> VimMapper {
                 return Ok(());
             },
             Action::ChangeMode => {
+                //Leaving the text modes ends any edge label edit
+                if !(KeybindMode::Edit | KeybindMode::Insert | KeybindMode::Visual).contains(payload.mode.unwrap()) {
+                    self.edited_edge = None;
+                    self.edited_note = None;
+                    self.input_manager.set_multiline(false);
+                }
                 match payload.mode {
                     Some(KeybindMode::Move) => {
                         if let Some(active_idx) = self.get_active_node_idx() {
@@ -1252,10 +2012,17 @@ impl<'a> VimMapper {
                             self.set_render_mode(NodeRenderMode::AllEnabled);
                             self.build_target_list_from_neighbors(idx);
                             self.cycle_target_forward();
+                        } else if let Some(idx) = self.get_target_node_idx() {
+                            //The best match may be inside a fold
+                            self.unfold_to_node(idx);
                         }
                     },
                     Some(KeybindMode::Edit) | Some(KeybindMode::Insert) | Some(KeybindMode::Visual) => {
-                        if let Some(active_node) = self.nodes.get_mut(&self.get_active_node_idx().unwrap()) {
+                        if let Some(edge) = self.edited_edge.and_then(|idx| self.edges.get_mut(&idx)) {
+                            edge.text_input.set_keybind_mode(payload.mode.unwrap());
+                        } else if let Some(node) = self.edited_note.and_then(|idx| self.nodes.get_mut(&idx)) {
+                            node.note_input.set_keybind_mode(payload.mode.unwrap());
+                        } else if let Some(active_node) = self.nodes.get_mut(&self.get_active_node_idx().unwrap()) {
                             // self.input_manager.text_input.text = active_node.label.clone();
                             // let _ = self.input_manager.text_input.set_cursor(Some(active_node.text_cursor_index));
                             // active_node.save_text();
@@ -1376,11 +2143,144 @@ impl<'a> VimMapper {
                 }
                 return Ok(());
             },
+            Action::EditTargetEdge => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    if let Some(edge_idx) = self.get_edge_between(active_idx, target_idx) {
+                        let edge = self.edges.get_mut(&edge_idx).unwrap();
+                        self.input_manager.set_keybind_mode(KeybindMode::Insert);
+                        edge.text_input.set_keybind_mode(KeybindMode::Insert);
+                        edge.load_input_text();
+                        edge.text_input.cursor_to_end();
+                        self.edited_edge = Some(edge_idx);
+                        ctx.request_layout();
+                    }
+                }
+                return Ok(());
+            },
+            Action::EditActiveNodeNote => {
+                if let Some(idx) = self.get_active_node_idx() {
+                    let active_node = self.nodes.get_mut(&idx).unwrap();
+                    self.input_manager.set_keybind_mode(KeybindMode::Edit);
+                    self.input_manager.set_multiline(true);
+                    active_node.load_note_input_text();
+                    active_node.note_input.set_keybind_mode(KeybindMode::Edit);
+                    active_node.note_input.cursor_to_start();
+                    self.edited_note = Some(idx);
+                    ctx.request_layout();
+                }
+                return Ok(());
+            },
+            Action::ToggleTargetEdgeArrow => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    self.toggle_edge_arrow(active_idx, target_idx);
+                    ctx.request_paint();
+                }
+                return Ok(());
+            },
+            Action::ToggleActiveEdgeArrow => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    self.toggle_edge_arrow(target_idx, active_idx);
+                    ctx.request_paint();
+                }
+                return Ok(());
+            },
+            Action::LinkTargetNode => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    if let Ok(_) = self.link_nodes(active_idx, target_idx) {
+                        self.build_target_list_from_neighbors(active_idx);
+                        let _ = self.target_node_if_listed(target_idx);
+                        ctx.request_layout();
+                    }
+                }
+                return Ok(());
+            },
+            Action::LinkMarkedNode => {
+                if let (Some(active_idx), Some(marked_idx)) = (self.get_active_node_idx(), self.get_node_by_mark(payload.string.clone().unwrap())) {
+                    if let Ok(_) = self.link_nodes(active_idx, marked_idx) {
+                        self.build_target_list_from_neighbors(active_idx);
+                        let _ = self.target_node_if_listed(marked_idx);
+                        ctx.request_layout();
+                    }
+                }
+                return Ok(());
+            },
+            Action::UnlinkTargetNode => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    if let Ok(_) = self.unlink_nodes(active_idx, target_idx) {
+                        self.build_target_list_from_neighbors(active_idx);
+                        if self.target_node_list.len() > 0 {
+                            self.cycle_target_forward();
+                        }
+                        ctx.request_layout();
+                    }
+                }
+                return Ok(());
+            },
+            Action::ApplyNodeStyle => {
+                if let Some(idx) = self.get_active_node_idx() {
+                    self.apply_node_style(idx, payload.string.clone().unwrap());
+                    ctx.request_layout();
+                }
+                return Ok(());
+            },
+            Action::SetNodeLink => {
+                if let Some(idx) = self.get_active_node_idx() {
+                    let link = payload.string.clone().unwrap_or_default().trim().to_string();
+                    self.nodes.get_mut(&idx).unwrap().link = if link.is_empty() { None } else { Some(link) };
+                    ctx.request_paint();
+                }
+                return Ok(());
+            },
+            Action::SetNodeMetadata => {
+                if let Some(idx) = self.get_active_node_idx() {
+                    let node = self.nodes.get_mut(&idx).unwrap();
+                    node.set_metadata_from_string(&payload.string.clone().unwrap_or_default());
+                    ctx.request_paint();
+                }
+                return Ok(());
+            },
+            Action::CycleLayoutMode => {
+                self.cycle_layout_mode();
+                return Ok(());
+            },
+            Action::ToggleMinimap => {
+                self.toggle_minimap();
+                return Ok(());
+            },
+            Action::ToggleNodeFold => {
+                if let Some(idx) = self.get_active_node_idx() {
+                    self.toggle_node_fold(idx);
+                    self.build_target_list_from_neighbors(idx);
+                    self.cycle_target_forward();
+                    ctx.request_layout();
+                }
+                return Ok(());
+            },
+            Action::OpenNodeFold => {
+                if let Some(idx) = self.get_active_node_idx() {
+                    self.unfold_node(idx);
+                    self.build_target_list_from_neighbors(idx);
+                    self.cycle_target_forward();
+                    ctx.request_layout();
+                }
+                return Ok(());
+            },
+            Action::CloseNodeFold => {
+                if let Some(idx) = self.get_active_node_idx() {
+                    self.fold_node(idx);
+                    self.build_target_list_from_neighbors(idx);
+                    self.cycle_target_forward();
+                    ctx.request_layout();
+                }
+                return Ok(());
+            },
             Action::CycleNodeForward => {
                 if let Some(_) = self.get_active_node_idx() {
                     self.cycle_target_forward();
                     if let Some(idx) = self.get_target_node_idx() {
-                        self.scroll_node_into_view(idx)
+                        //Search results may be inside a fold
+                        self.unfold_to_node(idx);
+                        self.bring_node_into_view(idx)
                     }
                 } else {
                     self.set_node_as_active(0);
@@ -1392,7 +2292,8 @@ impl<'a> VimMapper {
                 if let Some(_) = self.get_active_node_idx() {
                     self.cycle_target_backward();
                     if let Some(idx) = self.get_target_node_idx() {
-                        self.scroll_node_into_view(idx)
+                        self.unfold_to_node(idx);
+                        self.bring_node_into_view(idx)
                     }
                 } else {
                     self.set_node_as_active(0);
@@ -1573,6 +2474,24 @@ impl<'a> VimMapper {
                 }
                 return Ok(());
             }
+            Action::LengthenTargetEdge => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    self.change_edge_length(active_idx, target_idx, DEFAULT_EDGE_LENGTH_STEP);
+                }
+                return Ok(());
+            }
+            Action::ShortenTargetEdge => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    self.change_edge_length(active_idx, target_idx, -DEFAULT_EDGE_LENGTH_STEP);
+                }
+                return Ok(());
+            }
+            Action::ResetTargetEdgeLength => {
+                if let (Some(active_idx), Some(target_idx)) = (self.get_active_node_idx(), self.get_target_node_idx()) {
+                    self.reset_edge_length(active_idx, target_idx);
+                }
+                return Ok(());
+            }
             Action::ToggleNodeAnchor => {
                 if let Some(idx) = payload.index {
                     self.toggle_node_anchor(idx);
@@ -1621,8 +2540,9 @@ impl<'a> VimMapper {
             },
             Action::JumpToMarkedNode => {
                 if let Some(marked_idx) = self.get_node_by_mark(payload.string.clone().unwrap()) {
+                    //Activating a node inside a fold unfolds it
                     self.set_node_as_active(marked_idx);
-                    self.scroll_node_into_view(marked_idx);
+                    self.bring_node_into_view(marked_idx);
                 }
                 return Ok(());
             },
@@ -1646,8 +2566,9 @@ impl<'a> VimMapper {
             Action::SearchNodes => {
                 if let Some(string) = payload.string.clone() {
                     self.search_string = string.clone();
-                    if let Ok(_) = self.build_target_list_from_string(string) {
-                    }
+                    //Folds are left alone while the search is typed. The result is only unfolded once it's
+                    // chosen by entering Select mode or cycling to it.
+                    let _ = self.build_target_list_from_string(string);
                 }
                 return Ok(());
             },
@@ -1678,7 +2599,26 @@ impl<'a> VimMapper {
                 return Ok(());
             },
             Action::AcceptNodeText => {
-                if let Some(idx) = self.get_active_node_idx() {
+                if let Some(edge_idx) = self.edited_edge {
+                    if let Some(edge) = self.edges.get_mut(&edge_idx) {
+                        edge.text_input.handle_action(ctx,
+                            &ActionPayload {
+                                action: Action::ConfirmInserts,
+                                ..Default::default()
+                            }
+                        );
+                        edge.save_text();
+                        self.edge_layouts.remove(&edge_idx);
+                    }
+                } else if let Some(node) = self.edited_note.and_then(|idx| self.nodes.get_mut(&idx)) {
+                    node.note_input.handle_action(ctx,
+                        &ActionPayload {
+                            action: Action::ConfirmInserts,
+                            ..Default::default()
+                        }
+                    );
+                    node.save_note_text();
+                } else if let Some(idx) = self.get_active_node_idx() {
                     let node = self.nodes.get_mut(&idx).unwrap();
                     let node_fg = node.fg_index.unwrap();
                     // node.label = self.input_manager.text_input.text.clone();
@@ -1696,7 +2636,15 @@ impl<'a> VimMapper {
                 return Ok(());
             },
             Action::UndoNodeText => {
-                if let Some(active_idx) = self.get_active_node_idx() {
+                if let Some(edge_idx) = self.edited_edge {
+                    if let Some(edge) = self.edges.get_mut(&edge_idx) {
+                        edge.undo();
+                        self.edge_layouts.remove(&edge_idx);
+                    }
+                } else if let Some(node) = self.edited_note.and_then(|idx| self.nodes.get_mut(&idx)) {
+                    node.undo_note();
+                    ctx.request_layout();
+                } else if let Some(active_idx) = self.get_active_node_idx() {
                     let node = self.nodes.get_mut(&active_idx).unwrap();
                     let node_fg = node.fg_index.unwrap();
                     node.undo();
@@ -1705,7 +2653,15 @@ impl<'a> VimMapper {
                 return Ok(());
             },
             Action::RedoNodeText => {
-                if let Some(active_idx) = self.get_active_node_idx() {
+                if let Some(edge_idx) = self.edited_edge {
+                    if let Some(edge) = self.edges.get_mut(&edge_idx) {
+                        edge.redo();
+                        self.edge_layouts.remove(&edge_idx);
+                    }
+                } else if let Some(node) = self.edited_note.and_then(|idx| self.nodes.get_mut(&idx)) {
+                    node.redo_note();
+                    ctx.request_layout();
+                } else if let Some(active_idx) = self.get_active_node_idx() {
                     let node = self.nodes.get_mut(&active_idx).unwrap();
                     let node_fg = node.fg_index.unwrap();
                     node.redo();
@@ -1718,7 +2674,30 @@ impl<'a> VimMapper {
             Action::ConfirmInserts |
             Action::RollBackInserts |
             Action::InsertCharacter => {
-                if let Some(active_idx) = self.get_active_node_idx() {
+                if let Some(edge_idx) = self.edited_edge {
+                    if let Some(edge) = self.edges.get_mut(&edge_idx) {
+                        let ret = edge.text_input.handle_action(ctx, payload);
+                        self.edge_layouts.remove(&edge_idx);
+                        if let (Some(mode), _) = ret {
+                            self.input_manager.set_keybind_mode(mode);
+                            edge.text_input.set_keybind_mode(mode);
+                        }
+                        let (_, change) = ret;
+                        if change {
+                            edge.save_text();
+                        }
+                    }
+                } else if let Some(node) = self.edited_note.and_then(|idx| self.nodes.get_mut(&idx)) {
+                    let ret = node.note_input.handle_action(ctx, payload);
+                    if let (Some(mode), _) = ret {
+                        self.input_manager.set_keybind_mode(mode);
+                        node.note_input.set_keybind_mode(mode);
+                    }
+                    let (_, change) = ret;
+                    if change {
+                        node.save_note_text();
+                    }
+                } else if let Some(active_idx) = self.get_active_node_idx() {
                     // self.nodes.get_mut(&active_idx).unwrap().text_cursor_index = self.input_manager.text_input.get_cursor_index();
                     self.invalidate_node_layouts();
                     let active_node = self.nodes.get_mut(&active_idx).unwrap();
@@ -1749,10 +2728,12 @@ impl Widget<()> for VimMapper {
         //If the node editor is visible, pass events to it. Both events and paints must be withheld
         // for the widget to be truly hidden and uninteractable. 
         match event {
-            Event::AnimFrame(_interval) => {
+            Event::AnimFrame(interval) => {
                 // if self.is_hot && self.animating {
                 if self.animating {
-                    self.largest_node_movement = Some(self.graph.update(DEFAULT_UPDATE_DELTA));
+                    if let Some(movement) = self.advance_simulation(*interval) {
+                        self.largest_node_movement = Some(movement);
+                    }
                     if self.largest_node_movement < Some(ANIMATION_MOVEMENT_THRESHOLD) && self.animation_timer_token == None {
                         // self.animating = false;
                         self.animation_timer_token = Some(ctx.request_timer(DEFAULT_ANIMATION_TIMEOUT));
@@ -1762,6 +2743,13 @@ impl Widget<()> for VimMapper {
                 ctx.request_layout();
                 ctx.request_paint();
             }
+            Event::MouseDown(mouse_event) if mouse_event.button.is_left()
+                && self.minimap.map_or(false, |(rect, _)| rect.contains(mouse_event.pos)) => {
+                self.minimap_dragging = true;
+                self.pan_to_minimap_point(mouse_event.pos);
+                ctx.set_handled();
+                ctx.request_paint();
+            }
             Event::MouseDown(mouse_event) if mouse_event.button.is_left() => {
                 if self.input_manager.get_keybind_mode() == KeybindMode::Move {
                     ctx.submit_command(Command::new(EXECUTE_ACTION,
@@ -1923,6 +2911,11 @@ impl Widget<()> for VimMapper {
             }
             Event::MouseUp(_) => {
                 self.last_mouse_down_data = None;
+                self.minimap_dragging = false;
+            }
+            Event::MouseMove(mouse_event) if self.minimap_dragging => {
+                self.pan_to_minimap_point(mouse_event.pos);
+                ctx.request_paint();
             }
             Event::MouseMove(mouse_event) => {
                 if self.input_manager.get_keybind_mode() == KeybindMode::Move && self.last_mouse_down_data == None {
@@ -2056,15 +3049,23 @@ impl Widget<()> for VimMapper {
             self.translate = TranslateScale::new((vec.to_vec2()/2.0)+Vec2::new(self.offset_x, self.offset_y), 1.0);
         }
 
+        if self.folds_stale {
+            self.refresh_folds();
+        }
+
         self.graph.visit_nodes_mut(|fg_node| {
             let node = self.nodes.get_mut(&fg_node.data.user_data).unwrap();
+            //The sheet shows labels with their markdown rendered. The raw markup is only shown by the
+            // text input while the label is being edited.
+            let (label, spans) = parse_inline_markdown(&node.get_label());
             if let None = self.enabled_layouts.get(&fg_node.index()) {
                 if let Ok(layout) = VimMapper::build_label_layout_for_constraints(
-                    ctx.text(), node.get_label(), BoxConstraints::new(
+                    ctx.text(), label.clone(), &spans, BoxConstraints::new(
                         Size::new(0., 0.),
                         Size::new(NODE_LABEL_MAX_CONSTRAINTS.0, NODE_LABEL_MAX_CONSTRAINTS.1)
                     ),
-                    &self.config.get_color(VMColor::LabelTextColor).ok().expect("Couldn't find label text color in config."),
+                    &node.style.get_text_color().unwrap_or(self.config.get_color(VMColor::LabelTextColor).ok().expect("Couldn't find label text color in config.")),
+                    &node.style,
                 ) {
                     self.enabled_layouts.insert(fg_node.index(), layout.clone());
                     if !self.input_manager.get_keybind_mode().contains(KeybindMode::Insert) {
@@ -2073,27 +3074,85 @@ impl Widget<()> for VimMapper {
                         } else {
                             fg_node.data.repel_distance = layout.size().width;
                         }
+                        //Collisions use the box the node is painted with, which is the label inflated by
+                        // the border
+                        fg_node.data.half_extent = Some((
+                            fg_node.data.repel_distance / 2. + DEFAULT_BORDER_WIDTH,
+                            layout.size().height / 2. + DEFAULT_BORDER_WIDTH,
+                        ));
                     }
                 } 
             }
             if let None = self.disabled_layouts.get(&fg_node.index()) {
                 if let Ok(layout) = VimMapper::build_label_layout_for_constraints(
-                    ctx.text(), node.get_label(), BoxConstraints::new(
+                    ctx.text(), label, &spans, BoxConstraints::new(
                         Size::new(0., 0.),
                         Size::new(NODE_LABEL_MAX_CONSTRAINTS.0, NODE_LABEL_MAX_CONSTRAINTS.1)
                     ),
-                        &self.config.get_color(VMColor::DisabledLabelTextColor).ok().expect("Couldn't find disabled label text color in config."),
+                        &node.style.get_text_color().map(|color| color.with_alpha(0.5)).unwrap_or(self.config.get_color(VMColor::DisabledLabelTextColor).ok().expect("Couldn't find disabled label text color in config.")),
+                        &node.style,
                 ) {
                     self.disabled_layouts.insert(fg_node.index(), layout.clone());
                 } 
             }
         });
 
+        //Search results are listed with the characters that matched the search highlighted
+        if (KeybindMode::SearchEntry | KeybindMode::SearchedSheet).contains(self.input_manager.get_keybind_mode()) {
+            let highlight_color = self.config.get_color(VMColor::ActiveNodeBorderColor).expect("Couldn't find active node border color in config.");
+            for (idx, ranges) in &self.search_matches {
+                if self.search_layouts.contains_key(idx) {
+                    continue;
+                }
+                if let Some(node) = self.nodes.get(idx) {
+                    let (label, mut spans) = parse_inline_markdown(&node.get_label());
+                    //Skip ranges left over from before the label was edited
+                    spans.extend(ranges.iter()
+                        .filter(|range| label.get(range.start..range.end).is_some())
+                        .map(|range| InlineSpan { range: range.clone(), style: InlineStyle::Highlight(highlight_color.clone()) }));
+                    if let Ok(layout) = VimMapper::build_label_layout_for_constraints(
+                        ctx.text(), label, &spans, BoxConstraints::new(
+                            Size::new(0., 0.),
+                            Size::new(NODE_LABEL_MAX_CONSTRAINTS.0, NODE_LABEL_MAX_CONSTRAINTS.1)
+                        ),
+                        &node.style.get_text_color().unwrap_or(self.config.get_color(VMColor::LabelTextColor).ok().expect("Couldn't find label text color in config.")),
+                        &node.style,
+                    ) {
+                        self.search_layouts.insert(*idx, layout);
+                    }
+                }
+            }
+        }
+
+        for (idx, edge) in &self.edges {
+            if let None = self.edge_layouts.get(idx) {
+                if let Some(label) = edge.get_label() {
+                    if let Ok(layout) = ctx.text().new_text_layout(label)
+                    .font(FontFamily::SANS_SERIF, DEFAULT_EDGE_LABEL_FONT_SIZE)
+                    .text_color(self.config.get_color(VMColor::LabelTextColor).ok().expect("Couldn't find label text color in config."))
+                    .max_width(NODE_LABEL_MAX_CONSTRAINTS.0)
+                    .build() {
+                        self.edge_layouts.insert(*idx, layout);
+                    }
+                }
+            }
+        }
+
         if let Some(active_idx) = self.get_active_node_idx() {
             let active_node = self.nodes.get_mut(&active_idx).unwrap();
-            active_node.text_input.layout(ctx, &self.config);
+            active_node.text_input.layout(ctx, &self.config, &active_node.style);
+        }
+
+        if let Some(edge) = self.edited_edge.and_then(|idx| self.edges.get_mut(&idx)) {
+            edge.text_input.layout(ctx, &self.config, &VMNodeStyle::default());
+        }
+
+        if let Some(node) = self.edited_note.and_then(|idx| self.nodes.get_mut(&idx)) {
+            node.note_input.layout_note(ctx, &self.config, DEFAULT_NOTE_PANEL_WIDTH - 2.*DEFAULT_STACK_PADDING);
         }
 
+        self.rebuild_spatial_index();
+
         return bc.max();
     }
 
@@ -2106,11 +3165,24 @@ impl Widget<()> for VimMapper {
         //Fill the canvas with background
         ctx.fill(ctx_rect, &self.config.get_color(VMColor::SheetBackgroundColor).ok().expect("sheet background color not found"));
 
+        //Only the nodes and edges that fall in the visible part of the sheet are painted
+        let screen_to_canvas = Affine::from(self.scale).inverse() * Affine::from(self.translate).inverse();
+        let visible_rect = Rect::from_points(
+            screen_to_canvas * Point::ZERO,
+            screen_to_canvas * Point::new(ctx_size.width, ctx_size.height))
+            .inflate(DEFAULT_CULLING_MARGIN, DEFAULT_CULLING_MARGIN);
+        let visible_nodes = self.spatial_index.query_rect(visible_rect);
 
         //Draw edges
         self.graph.visit_edges(|node1, node2, _edge| {
+            if node1.data.is_hidden || node2.data.is_hidden {
+                return;
+            }
             let p0 = Point::new(node1.x() as f64, node1.y() as f64);
             let p1 = Point::new(node2.x() as f64, node2.y() as f64);
+            if !line_may_cross(visible_rect, p0, p1) {
+                return;
+            }
             let path = Line::new(p0, p1);
             ctx.with_save(|ctx| {
                 ctx.transform(Affine::from(self.translate));
@@ -2127,6 +3199,70 @@ impl Widget<()> for VimMapper {
             });
         });
 
+        //Draw arrowheads with their tips on the border of the node they point to. Node rects are cached in
+        // screen space, so only their size (scaled back to canvas space) is used here.
+        self.graph.visit_edges(|node1, node2, edge| {
+            let direction = self.edges.get(&edge.user_data).map_or(EdgeDirection::None, |e| e.get_direction());
+            if direction == EdgeDirection::None || node1.data.is_hidden || node2.data.is_hidden {
+                return;
+            }
+            let p0 = Point::new(node1.x(), node1.y());
+            let p1 = Point::new(node2.x(), node2.y());
+            if !line_may_cross(visible_rect, p0, p1) {
+                return;
+            }
+            let scale = self.scale.as_tuple().1;
+            let mut arrows = vec![];
+            if direction.points_to_target() {
+                arrows.push((p1, p0, &self.nodes[&node2.data.user_data]));
+            }
+            if direction.points_to_source() {
+                arrows.push((p0, p1, &self.nodes[&node1.data.user_data]));
+            }
+            ctx.with_save(|ctx| {
+                ctx.transform(Affine::from(self.translate));
+                ctx.transform(Affine::from(self.scale));
+                for (center, from, node) in arrows {
+                    //Skip nodes that haven't been painted yet
+                    if node.node_rect.is_empty() {
+                        continue;
+                    }
+                    //Pad by the widest border stroke so the tip isn't hidden under it
+                    let size = node.node_rect.size() / scale + Size::new(DEFAULT_ACTIVE_BORDER_WIDTH, DEFAULT_ACTIVE_BORDER_WIDTH);
+                    let radius = node.style.get_border_radius(&size.to_rect());
+                    let tip = clip_to_rounded_rect(center, size, radius, from);
+                    ctx.fill(build_arrowhead(tip, from), &self.config.get_color(VMColor::EdgeColor).ok().expect("edge color not found in config"));
+                }
+            });
+        });
+
+        //Draw edge labels over the edges at their midpoints. The midpoint of an edge being edited is kept
+        // so that its text input can be drawn there later.
+        let mut edited_edge_midpoint: Option<Point> = None;
+        self.graph.visit_edges(|node1, node2, edge| {
+            let midpoint = Point::new(node1.x(), node1.y()).lerp(Point::new(node2.x(), node2.y()), 0.5);
+            if Some(edge.user_data) == self.edited_edge {
+                edited_edge_midpoint = Some(midpoint);
+                return;
+            }
+            if node1.data.is_hidden || node2.data.is_hidden {
+                return;
+            }
+            if let Some(layout) = self.edge_layouts.get(&edge.user_data) {
+                if !visible_rect.contains(midpoint) {
+                    return;
+                }
+                ctx.with_save(|ctx| {
+                    ctx.transform(Affine::from(self.translate));
+                    ctx.transform(Affine::from(self.scale));
+                    ctx.transform(Affine::translate(midpoint.to_vec2() - layout.size().to_vec2()/2.));
+                    let rect = layout.size().to_rect().inflate(DEFAULT_EDGE_LABEL_PADDING, DEFAULT_EDGE_LABEL_PADDING);
+                    ctx.fill(rect.to_rounded_rect(DEFAULT_BORDER_RADIUS), &self.config.get_color(VMColor::SheetBackgroundColor).ok().expect("sheet background color not found"));
+                    ctx.draw_text(layout, Point::ZERO);
+                });
+            }
+        });
+
         //Determine target node for painting
         let target_node: Option<u32> = self.get_target_node_idx();
 
@@ -2134,6 +3270,9 @@ impl Widget<()> for VimMapper {
 
         //Draw nodes except active and target
         self.graph.visit_nodes(|fg_node| {
+            if fg_node.data.is_hidden || !visible_nodes.contains(&fg_node.index()) {
+                return;
+            }
             let node = self.nodes.get_mut(&fg_node.data.user_data)
             .expect("Expected non-option node in paint loop.");
             let node_pos = Vec2::new(self.graph.get_graph()[node.fg_index.unwrap()].x(), self.graph.get_graph()[node.fg_index.unwrap()].y());
@@ -2225,7 +3364,7 @@ impl Widget<()> for VimMapper {
                         &self.graph,
                         enabled,
                         true,
-                        if self.input_manager.get_keybind_mode() != KeybindMode::Insert && self.input_manager.get_keybind_mode() != KeybindMode::Edit {
+                        if (self.input_manager.get_keybind_mode() != KeybindMode::Insert && self.input_manager.get_keybind_mode() != KeybindMode::Edit) || self.edited_edge.is_some() || self.edited_note.is_some() {
                             if enabled {Some(&self.enabled_layouts[&node.fg_index.unwrap()])} else {Some(&self.disabled_layouts[&node.fg_index.unwrap()])}
                         } else {
                             None
@@ -2239,9 +3378,9 @@ impl Widget<()> for VimMapper {
             
 
             //Render input label and cursor boxes if necessary
-            if self.input_manager.get_keybind_mode() == KeybindMode::Insert || 
+            if (self.input_manager.get_keybind_mode() == KeybindMode::Insert || 
                 self.input_manager.get_keybind_mode() == KeybindMode::Edit || 
-                self.input_manager.get_keybind_mode() == KeybindMode::Visual {
+                self.input_manager.get_keybind_mode() == KeybindMode::Visual) && self.edited_edge.is_none() && self.edited_note.is_none() {
                 ctx.with_save(|ctx| {
                     let mut label_size = node.text_input.text_layout.as_ref().unwrap().size();
                     if label_size.width < DEFAULT_MIN_NODE_WIDTH_DATA {
@@ -2257,15 +3396,42 @@ impl Widget<()> for VimMapper {
             }
         }
 
+        //Render the input label and cursor of an edge being edited
+        if let Some(edge_idx) = self.edited_edge {
+            if let Some(midpoint) = edited_edge_midpoint {
+                let edge = self.edges.get_mut(&edge_idx).unwrap();
+                if let Some(mut label_size) = edge.text_input.text_layout.as_ref().map(|layout| layout.size()) {
+                    if label_size.width < DEFAULT_MIN_NODE_WIDTH_DATA {
+                        label_size.width = DEFAULT_MIN_NODE_WIDTH_DATA;
+                    }
+                    ctx.with_save(|ctx| {
+                        ctx.transform(Affine::from(self.translate));
+                        ctx.transform(Affine::from(self.scale));
+                        ctx.transform(Affine::translate(midpoint.to_vec2() - label_size.to_vec2()/2.));
+                        let rect = label_size.to_rect().inflate(DEFAULT_EDGE_LABEL_PADDING, DEFAULT_EDGE_LABEL_PADDING);
+                        ctx.fill(rect.to_rounded_rect(DEFAULT_BORDER_RADIUS), &self.config.get_color(VMColor::SheetBackgroundColor).unwrap());
+                        ctx.stroke(rect.to_rounded_rect(DEFAULT_BORDER_RADIUS), &self.config.get_color(VMColor::ActiveNodeBorderColor).unwrap(), DEFAULT_MARK_BORDER_WIDTH);
+                        edge.text_input.paint(ctx, &self.config, self.debug_data);
+                    });
+                }
+            }
+        }
+
 
         if (KeybindMode::SearchEntry | KeybindMode::SearchedSheet).contains(self.input_manager.get_keybind_mode()) {
             if self.get_target_list_length() == 0 {
+                //An invalid regex is reported in place of the results so it can be fixed while typing
+                let (prefix, suffix, color) = if let Some(error) = &self.search_error {
+                    ("Invalid search \"", format!("\" — {}", error), VMColor::AlertColor)
+                } else {
+                    ("No results for \"", String::from("\""), VMColor::LabelTextColor)
+                };
                 let header_text = ctx.text().new_text_layout(
-                    format!("No results for \"{}\"", self.search_string)
+                    format!("{}{}{}", prefix, self.search_string, suffix)
                 )
-                .text_color(self.config.get_color(VMColor::LabelTextColor).expect("Couldn't get LabelTextColor"))
+                .text_color(self.config.get_color(color).expect("Couldn't get search header color"))
                 .font(FontFamily::SANS_SERIF, DEFAULT_SEARCH_TERM_FONT_SIZE)
-                .range_attribute(16..16+self.search_string.len(),
+                .range_attribute(prefix.len()..prefix.len()+self.search_string.len(),
                     if self.input_manager.get_keybind_mode().contains(KeybindMode::SearchEntry) {
                         TextAttribute::Underline(true)
                     } else {
@@ -2305,8 +3471,9 @@ impl Widget<()> for VimMapper {
                 let mut max_width: f64 = 0.;
                 for node_index in &self.target_node_list {
                     let node = self.nodes.get(node_index).unwrap().fg_index.unwrap();
-                    let width = (self.enabled_layouts.get(&node).unwrap().size().width * DEFAULT_STACK_SCALE) + (2. * DEFAULT_TARGET_BORDER_WIDTH) + DEFAULT_STACK_Y_BOTTOM_MARGIN;
-                    max_height += (self.enabled_layouts.get(&node).unwrap().size().height * DEFAULT_STACK_SCALE) + DEFAULT_STACK_SPACING;
+                    let layout = self.search_layouts.get(node_index).unwrap_or(&self.enabled_layouts[&node]);
+                    let width = (layout.size().width * DEFAULT_STACK_SCALE) + (2. * DEFAULT_TARGET_BORDER_WIDTH) + DEFAULT_STACK_Y_BOTTOM_MARGIN;
+                    max_height += (layout.size().height * DEFAULT_STACK_SCALE) + DEFAULT_STACK_SPACING;
                     if width > max_width {
                         max_width = width;
                     }
@@ -2345,14 +3512,15 @@ impl Widget<()> for VimMapper {
                     ctx.clip(pane_rect.inflate(-2., -2.));
                     for node_idx in &self.target_node_list {
                         let node = self.nodes.get_mut(node_idx).unwrap();
-                        let label_size = self.enabled_layouts[&node.fg_index.unwrap()].size().to_vec2();
+                        let layout = self.search_layouts.get(node_idx).unwrap_or(&self.enabled_layouts[&node.fg_index.unwrap()]);
+                        let label_size = layout.size().to_vec2();
                         node.paint_node(
                             ctx, 
                             0,
                             &self.graph,
                             true,
                             false,
-                            Some(&self.enabled_layouts[&node.fg_index.unwrap()]),
+                            Some(layout),
                             &self.config, 
                             target_node, 
                             label_size/2.,
@@ -2363,18 +3531,31 @@ impl Widget<()> for VimMapper {
                             }),
                             &TranslateScale::scale(DEFAULT_STACK_SCALE), 
                             self.debug_data); 
-                        y += (&self.enabled_layouts[&node.fg_index.unwrap()].size().height * DEFAULT_STACK_SCALE) + DEFAULT_STACK_SPACING;
+                        y += (layout.size().height * DEFAULT_STACK_SCALE) + DEFAULT_STACK_SPACING;
                     }
                 });
             }
         }
 
+        if self.input_manager.get_keybind_mode() == KeybindMode::Style {
+            self.paint_style_picker(ctx);
+        }
+
+        if self.edited_note.is_some() {
+            self.paint_note_panel(ctx);
+        }
+
+        if self.minimap_shown {
+            self.paint_minimap(ctx);
+        }
+
         //Paint debug dump
         if self.debug_data {
             let debug_text = format!(
-                "Animating: {}\nAnimation timer: {:?}\nIs hot:{}",
+                "Animating: {}\nAnimation timer: {:?}\nLayout: {:?}\nIs hot:{}",
                 self.animating,
                 self.animation_timer_token,
+                self.layout_mode,
                 self.is_hot,
             );
             let debug_layout = ctx.text().new_text_layout(debug_text)
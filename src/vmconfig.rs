@@ -17,7 +17,8 @@ use std::{collections::HashMap, fs};
 use druid::{Color};
 use serde::{Serialize, Deserialize};
 
-use crate::constants::{DEFAULT_CONFIG_DIR_NAME, DEFAULT_CONFIG_FILE_NAME, CURRENT_CONFIG_FILE_VERSION};
+use crate::constants::{DEFAULT_CONFIG_DIR_NAME, DEFAULT_CONFIG_FILE_NAME, CURRENT_CONFIG_FILE_VERSION, DEFAULT_LINK_OPENER, DEFAULT_SIMULATION_SPEED, DEFAULT_MINIMAP_SIZE};
+use crate::vmstyle::VMNamedStyle;
 
 #[allow(dead_code)]
 const VERSIONS: &'static [&'static str] = &["0.4.0"];
@@ -28,6 +29,14 @@ pub enum ColorScheme {
     DARK,
 }
 
+#[derive(PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
+pub enum MinimapCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(Debug,Hash,Eq,PartialEq,Clone,Serialize,Deserialize)]
 pub enum VMColor {
     LabelTextColor,
@@ -61,7 +70,22 @@ pub enum VMColor {
 pub struct VMConfigVersion4 {
     pub file_version: String,
     pub menu_shown: Option<bool>,
+    //Reopen the sheet and viewport from the last session on launch when no file is given
+    pub reopen_last_session: Option<bool>,
     pub color_scheme: ColorScheme,
+    //Node styles offered by the style picker. Older configs without this fall back to the defaults.
+    node_styles: Option<Vec<VMNamedStyle>>,
+    //Command used to open node links that aren't sheets. The link is passed as the last argument.
+    // Falls back to the platform default (xdg-open on Linux) when absent.
+    link_opener: Option<String>,
+    //Seed for the placement of new nodes in new sheets. Sheets created with a seed lay out the same way on
+    // every machine. The seed is saved with the sheet, so changing this doesn't affect existing sheets.
+    layout_seed: Option<u64>,
+    //Multiplier applied to the rate of simulation steps. Values above 1 settle sheets faster.
+    simulation_speed: Option<f64>,
+    //Corner of the sheet the minimap is painted in and its width and height in pixels
+    minimap_corner: Option<MinimapCorner>,
+    minimap_size: Option<(f64, f64)>,
     dark_palette: HashMap<VMColor, (u8,u8,u8,u8)>,
     light_palette: HashMap<VMColor, (u8,u8,u8,u8)>,
 }
@@ -198,7 +222,14 @@ impl Default for VMConfigVersion4 {
         let mut config = VMConfigVersion4 {
             file_version: String::from(CURRENT_CONFIG_FILE_VERSION.to_string()),
             menu_shown: Some(true),
+            reopen_last_session: Some(false),
             color_scheme: ColorScheme::LIGHT,
+            node_styles: Some(VMNamedStyle::default_styles()),
+            link_opener: None,
+            layout_seed: None,
+            simulation_speed: Some(DEFAULT_SIMULATION_SPEED),
+            minimap_corner: Some(MinimapCorner::BottomRight),
+            minimap_size: Some(DEFAULT_MINIMAP_SIZE),
             light_palette,
             dark_palette,
         };
@@ -239,6 +270,52 @@ impl VMConfigVersion4 {
         }
     }
 
+    pub fn get_reopen_last_session(&self) -> bool {
+        if let Some(reopen) = self.reopen_last_session {
+            return reopen;
+        } else {
+            return false;
+        }
+    }
+
+    pub fn get_node_styles(&self) -> Vec<VMNamedStyle> {
+        if let Some(styles) = &self.node_styles {
+            return styles.clone();
+        } else {
+            return VMNamedStyle::default_styles();
+        }
+    }
+
+    pub fn get_link_opener(&self) -> String {
+        if let Some(opener) = &self.link_opener {
+            return opener.clone();
+        } else {
+            return DEFAULT_LINK_OPENER.to_string();
+        }
+    }
+
+    pub fn get_layout_seed(&self) -> Option<u64> {
+        return self.layout_seed;
+    }
+
+    pub fn get_simulation_speed(&self) -> f64 {
+        match self.simulation_speed {
+            Some(speed) if speed > 0. => return speed,
+            _ => return DEFAULT_SIMULATION_SPEED,
+        }
+    }
+
+    pub fn get_minimap_corner(&self) -> MinimapCorner {
+        return self.minimap_corner.unwrap_or(MinimapCorner::BottomRight);
+    }
+
+    pub fn get_minimap_size(&self) -> (f64, f64) {
+        match self.minimap_size {
+            Some((width, height)) if width > 0. && height > 0. => return (width, height),
+            _ => return DEFAULT_MINIMAP_SIZE,
+        }
+    }
+
     pub fn get_color(&self, key: VMColor) -> Result<Color, String> {
         match self.color_scheme {
             ColorScheme::LIGHT => {
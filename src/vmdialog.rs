@@ -16,8 +16,9 @@ use druid::{widget::{Flex, SizedBox, Label, MainAxisAlignment, Controller, TextB
 
 use core::fmt::Debug;
 use std::fmt::Display;
+use std::path::PathBuf;
 
-use crate::{vmconfig::{VMConfigVersion4, VMColor}, vminput::{Action, ActionPayload}, vmsave::VMSaveState};
+use crate::{vmconfig::{VMConfigVersion4, VMColor}, vminput::{Action, ActionPayload}, vmsave::VMSaveState, vmrecent::VMRecentVersion1};
 
 use crate::constants::*;
 
@@ -31,6 +32,8 @@ pub struct VMDialog {
 pub struct VMDialogParams {
     pub buttons: Vec<(String, Vec<ActionPayload>, bool)>,
     pub prompts: Vec<(String, Option<VMColor>)>,
+    //Buttons stacked vertically between the prompts and the button row
+    pub list: Vec<(String, Vec<ActionPayload>)>,
     // pub input: Option<(String, ActionPayload)>,
 }
 
@@ -185,6 +188,25 @@ impl VMDialog {
             main_column.add_default_spacer();
         }
 
+        for (label, payloads) in &params.list {
+            let payloads = payloads.clone();
+            main_column.add_child(
+                VMButton::<String>::new(
+                    config,
+                    label.clone(),
+                move |ctx| {
+                    ctx.submit_command(
+                        Command::new(
+                            DIALOG_EXECUTE_ACTIONS,
+                            payloads.clone(),
+                            Target::Auto
+                        )
+                    )
+                }, false).controller(VMDialogController)
+            );
+            main_column.add_default_spacer();
+        }
+
         main_column.add_spacer(DIALOG_LABEL_BUTTON_SPACER);
 
         let mut button_row = Flex::<String>::row();
@@ -330,35 +352,61 @@ impl VMDialog {
         }
     }
 
-    pub fn make_start_dialog_params() -> VMDialogParams {
+    pub fn make_start_dialog_params(recent_files: &Vec<PathBuf>, has_last_session: bool) -> VMDialogParams {
+        let mut prompts = vec![
+            ("Do you want create a new sheet or load an existing one?".to_string(), None)
+        ];
+        if recent_files.len() > 0 {
+            prompts.push((String::from("Recent sheets:"), None));
+        }
+        let mut buttons = vec![
+            (
+                String::from("New"),
+                vec![ActionPayload {
+                    action: Action::CreateNewSheet,
+                    ..Default::default()
+                }],
+                false
+            ),
+            (
+                String::from("Open"),
+                vec![ActionPayload {
+                    action: Action::OpenExistingSheet,
+                    ..Default::default()
+                }],
+                false
+            )
+        ];
+        if has_last_session {
+            buttons.push((
+                String::from("Reopen Last Session"),
+                vec![ActionPayload {
+                    action: Action::ReopenLastSession,
+                    ..Default::default()
+                }],
+                false
+            ));
+        }
         VMDialogParams {
-            prompts: vec![
-                ("Do you want create a new sheet or load an existing one?".to_string(), None)
-            ],
-            buttons: vec![
-                (
-                    String::from("New"),
-                    vec![ActionPayload {
-                        action: Action::CreateNewSheet,
-                        ..Default::default()
-                    }],
-                    false
-                ),
+            list: recent_files.iter().map(|path| {
                 (
-                    String::from("Open"),
+                    VMRecentVersion1::display_path(path),
                     vec![ActionPayload {
-                        action: Action::OpenExistingSheet,
+                        action: Action::OpenRecentSheet,
+                        path: Some(path.clone()),
                         ..Default::default()
-                    }],
-                    false
+                    }]
                 )
-            ],
+            }).collect(),
+            prompts,
+            buttons,
             // input: None
         }
     }
 
     pub fn make_save_and_quit_dialog_params() -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             prompts: vec![
                 (String::from("This sheet has unsaved changes, do you want to save before closing this sheet?"), None)
             ],
@@ -404,6 +452,7 @@ impl VMDialog {
 
     pub fn make_save_and_new_dialog_params() -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             prompts: vec![
                 (String::from("This sheet has unsaved changes, do you want to save before closing this sheet?"), None)
             ],
@@ -451,8 +500,9 @@ impl VMDialog {
         }
     }
 
-    pub fn make_save_and_open_dialog_params() -> VMDialogParams {
+    pub fn make_save_and_open_dialog_params(open_action: ActionPayload) -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             prompts: vec![
                 (String::from("This sheet has unsaved changes, do you want to save before closing this sheet?"), None)
             ],
@@ -469,10 +519,7 @@ impl VMDialog {
                             save_state: Some(VMSaveState::Saved),
                             ..Default::default()
                         },
-                        ActionPayload {
-                            action: Action::OpenExistingSheet,
-                            ..Default::default()
-                        }
+                        open_action.clone()
                     ],
                     false
                 ),
@@ -490,10 +537,7 @@ impl VMDialog {
                             save_state: Some(VMSaveState::DiscardChanges),
                             ..Default::default()
                         },
-                        ActionPayload {
-                            action: Action::OpenExistingSheet,
-                            ..Default::default()
-                        }
+                        open_action.clone()
                     ],
                     true
                 ),
@@ -504,6 +548,7 @@ impl VMDialog {
 
     pub fn make_save_as_and_new_dialog_params() -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             prompts: vec![
                 (String::from("This sheet is unsaved, do you want to save before closing this sheet?"), None),
                 (String::from("Existing files will be overwritten!"), Some(VMColor::AlertColor))
@@ -549,8 +594,9 @@ impl VMDialog {
         }
     }
 
-    pub fn make_save_as_and_open_dialog_params() -> VMDialogParams {
+    pub fn make_save_as_and_open_dialog_params(open_action: ActionPayload) -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             prompts: vec![
                 (String::from("This sheet is unsaved, do you want to save before closing this sheet?"), None),
                 (String::from("Existing files will be overwritten!"), Some(VMColor::AlertColor))
@@ -584,10 +630,7 @@ impl VMDialog {
                             save_state: Some(VMSaveState::DiscardChanges),
                             ..Default::default()
                         },
-                        ActionPayload {
-                            action: Action::OpenExistingSheet,
-                            ..Default::default()
-                        }
+                        open_action.clone()
                     ],
                     true
                 ),
@@ -598,6 +641,7 @@ impl VMDialog {
 
     pub fn make_save_as_and_quit_dialog_params() -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             prompts: vec![
                 (String::from("This sheet is unsaved, do you want to save before closing this sheet?"), None),
                 (String::from("Existing files will be overwritten!"), Some(VMColor::AlertColor))
@@ -643,9 +687,48 @@ impl VMDialog {
         }
     }
 
+    pub fn make_external_change_dialog_params() -> VMDialogParams {
+        VMDialogParams {
+            list: vec![],
+            prompts: vec![
+                (String::from("This sheet has been changed outside of Vim-Mapper."), None),
+                (String::from("Reloading will discard your unsaved changes!"), Some(VMColor::AlertColor))
+            ],
+            buttons: vec![
+                (
+                    String::from("Keep Mine"),
+                    vec![ActionPayload { action: Action::NullAction, ..Default::default() }],
+                    false
+                ),
+                (
+                    String::from("Save As..."),
+                    vec![
+                        ActionPayload {
+                            action: Action::SaveSheetAs,
+                            ..Default::default()
+                        },
+                    ],
+                    false
+                ),
+                (
+                    String::from("Reload"),
+                    vec![
+                        ActionPayload {
+                            action: Action::ReloadSheetFromDisk,
+                            ..Default::default()
+                        },
+                    ],
+                    true
+                ),
+            ],
+            // input: None
+        }
+    }
+
     #[allow(dead_code)]
     pub fn make_overwrite_prompt_dialog_params() -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             prompts: vec![
                 (String::from("This file already exists. Do you want to overwrite?"), None)
             ],
@@ -672,6 +755,7 @@ impl VMDialog {
 
     pub fn make_cut_node_prompt_dialog_params(count: usize, remove_idx: u32) -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             buttons: vec![
                 (
                     String::from("Cancel"),
@@ -706,6 +790,7 @@ impl VMDialog {
 
     pub fn make_delete_tab_prompt_dialog_params(tab_idx: usize) -> VMDialogParams {
         VMDialogParams {
+            list: vec![],
             buttons: vec![
                 (
                     String::from("Cancel"),
@@ -767,4 +852,56 @@ impl VMDialog {
             ],
         }
     }
+
+    pub fn make_node_metadata_input_params() -> VMInputParams {
+        VMInputParams {
+            prompts: vec![
+                (String::from("Enter tags and properties for this node."), None),
+                (String::from("e.g. #urgent #backend owner:alice status:\"in review\""), None),
+            ],
+            button: (String::from("Ok"), false),
+            input_actions: vec![
+                ActionPayload {
+                    action: Action::SetNodeMetadata,
+                    ..Default::default()
+                }
+            ],
+        }
+    }
+
+    pub fn make_simulation_parameters_input_params(error: Option<String>) -> VMInputParams {
+        let mut prompts = vec![
+            (String::from("Set the simulation parameters for this tab."), None),
+            (String::from("e.g. charge=1500 spring=2 damping=0.6 min_attract=240 theta=none"), None),
+        ];
+        if let Some(error) = error {
+            prompts.push((error, Some(VMColor::AlertColor)));
+        }
+        VMInputParams {
+            prompts,
+            button: (String::from("Ok"), false),
+            input_actions: vec![
+                ActionPayload {
+                    action: Action::SetSimulationParameters,
+                    ..Default::default()
+                }
+            ],
+        }
+    }
+
+    pub fn make_node_link_input_params() -> VMInputParams {
+        VMInputParams {
+            prompts: vec![
+                (String::from("Enter a URL or file path for this node. Leave empty to remove the link."), None),
+                (String::from("Link to a node in another sheet with path/to/sheet.vmd#<mark>."), None),
+            ],
+            button: (String::from("Ok"), false),
+            input_actions: vec![
+                ActionPayload {
+                    action: Action::SetNodeLink,
+                    ..Default::default()
+                }
+            ],
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+// Copyright 2022 Doug Powers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::kurbo::BezPath;
+use druid::{Point, Size, Vec2};
+use serde::{Serialize, Deserialize};
+
+use crate::constants::*;
+use crate::vmtextinput::VMTextInput;
+
+//Which ends of an edge carry an arrowhead. Forward and Backward are relative to the source and target
+// stored in the ForceGraph for that edge, not to the active node.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EdgeDirection {
+    None,
+    Forward,
+    Backward,
+    Both,
+}
+
+impl Default for EdgeDirection {
+    fn default() -> Self {
+        EdgeDirection::None
+    }
+}
+
+impl EdgeDirection {
+    pub fn points_to_target(&self) -> bool {
+        return *self == EdgeDirection::Forward || *self == EdgeDirection::Both;
+    }
+
+    pub fn points_to_source(&self) -> bool {
+        return *self == EdgeDirection::Backward || *self == EdgeDirection::Both;
+    }
+
+    fn from_ends(to_source: bool, to_target: bool) -> Self {
+        match (to_source, to_target) {
+            (false, false) => EdgeDirection::None,
+            (false, true) => EdgeDirection::Forward,
+            (true, false) => EdgeDirection::Backward,
+            (true, true) => EdgeDirection::Both,
+        }
+    }
+
+    //The same direction as seen from an edge with its source and target swapped.
+    pub fn reversed(&self) -> Self {
+        return EdgeDirection::from_ends(self.points_to_target(), self.points_to_source());
+    }
+
+    pub fn toggle_target(&self) -> Self {
+        return EdgeDirection::from_ends(self.points_to_source(), !self.points_to_target());
+    }
+
+    pub fn toggle_source(&self) -> Self {
+        return EdgeDirection::from_ends(!self.points_to_source(), self.points_to_target());
+    }
+}
+
+//The VimMapper-side data for an edge. The ForceGraph EdgeData user_data holds the u32 key to this
+// struct in the VimMapper edges HashMap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VMEdge {
+    pub index: u32,
+    label: Option<String>,
+    #[serde(default)]
+    direction: EdgeDirection,
+    #[serde(skip)]
+    pub text_input: VMTextInput,
+}
+
+impl Default for VMEdge {
+    fn default() -> Self {
+        VMEdge {
+            index: 0,
+            label: None,
+            direction: EdgeDirection::None,
+            text_input: VMTextInput::new(String::new(), None),
+        }
+    }
+}
+
+impl VMEdge {
+    pub fn with_fields(label: Option<String>, direction: EdgeDirection, index: u32) -> Self {
+        VMEdge {
+            index,
+            text_input: VMTextInput::new(label.clone().unwrap_or_default(), None),
+            label,
+            direction,
+        }
+    }
+
+    pub fn set_direction(&mut self, direction: EdgeDirection) {
+        self.direction = direction;
+    }
+
+    pub fn get_direction(&self) -> EdgeDirection {
+        self.direction
+    }
+
+    pub fn set_label(&mut self, label: Option<String>) {
+        self.label = label;
+    }
+
+    pub fn get_label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    //Empty labels are stored as None so unlabeled edges aren't drawn with an empty box.
+    fn label_from_input(&mut self) {
+        let text = self.text_input.get_text();
+        if text.is_empty() {
+            self.label = None;
+        } else {
+            self.label = Some(text);
+        }
+    }
+
+    pub fn save_text(&mut self) {
+        self.label_from_input();
+        self.text_input.push_history();
+    }
+
+    pub fn undo(&mut self) {
+        self.text_input.undo();
+        self.label_from_input();
+    }
+
+    pub fn redo(&mut self) {
+        self.text_input.redo();
+        self.label_from_input();
+    }
+
+    pub fn load_input_text(&mut self) {
+        self.text_input.set_text(self.label.clone().unwrap_or_default());
+    }
+}
+
+//Returns the point where a ray cast from the center of a node's rounded rect toward another point leaves
+// the rect. All values are in canvas space. Arrowheads are drawn with their tip at this point so they
+// rest on the node border rather than under the node.
+pub fn clip_to_rounded_rect(center: Point, size: Size, radius: f64, toward: Point) -> Point {
+    let dir = toward - center;
+    if dir.hypot() == 0. {
+        return center;
+    }
+    let dir = dir.normalize();
+    let half_w = size.width / 2.;
+    let half_h = size.height / 2.;
+    let radius = radius.min(half_w).min(half_h);
+    let t_x = if dir.x == 0. { f64::INFINITY } else { half_w / dir.x.abs() };
+    let t_y = if dir.y == 0. { f64::INFINITY } else { half_h / dir.y.abs() };
+    let mut t = t_x.min(t_y);
+    let hit = dir * t;
+    //If the ray hits the square corner of the bounding rect, intersect with the corner's circle instead
+    if hit.x.abs() > half_w - radius && hit.y.abs() > half_h - radius {
+        let corner = Vec2::new(
+            (half_w - radius) * dir.x.signum(),
+            (half_h - radius) * dir.y.signum(),
+        );
+        let b = dir.dot(corner);
+        let c = corner.hypot2() - radius * radius;
+        let disc = b * b - c;
+        if disc >= 0. {
+            t = b + disc.sqrt();
+        }
+    }
+    return center + dir * t;
+}
+
+//Builds a closed arrowhead with its tip at `tip`, pointing away from `from`.
+pub fn build_arrowhead(tip: Point, from: Point) -> BezPath {
+    let mut path = BezPath::new();
+    let dir = tip - from;
+    if dir.hypot() == 0. {
+        return path;
+    }
+    let dir = dir.normalize();
+    let perp = Vec2::new(-dir.y, dir.x);
+    let base = tip - dir * DEFAULT_ARROWHEAD_LENGTH;
+    path.move_to(tip);
+    path.line_to(base + perp * (DEFAULT_ARROWHEAD_WIDTH / 2.));
+    path.line_to(base - perp * (DEFAULT_ARROWHEAD_WIDTH / 2.));
+    path.close_path();
+    return path;
+}
@@ -20,11 +20,13 @@ use serde::{Deserialize, Serialize};
 use vm_force_graph_rs::{Node, NodeData, DefaultNodeIdx, EdgeData};
 use petgraph::{stable_graph::StableUnGraph, visit::{EdgeRef, IntoEdgeReferences}};
 
-use crate::{vmnode::VMNode, vimmapper::VimMapper, VMTab, constants::SET_REGISTER, vmconfig::VMConfigVersion4, vminput::KeybindMode};
+use crate::{vmnode::VMNode, vmedge::VMEdge, vimmapper::VimMapper, VMTab, constants::SET_REGISTER, vmconfig::VMConfigVersion4, vminput::KeybindMode};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct VMGraphClip {
     nodes: HashMap<u32, VMNode>,
+    //Edge data keyed by the user_data of the clip graph's edges
+    edges: HashMap<u32, VMEdge>,
     graph: StableUnGraph<Node<u32>, EdgeData<u32>>,
     pub root_node: Option<DefaultNodeIdx>,
 }
@@ -33,6 +35,7 @@ impl VMGraphClip {
     pub fn dispatch(ctx: &mut EventCtx, mapper: &VimMapper, node_set: &HashSet<DefaultNodeIdx>, root: DefaultNodeIdx, _register_name: &String) {
         let mut graph_clip = VMGraphClip {
             nodes: HashMap::new(),
+            edges: HashMap::new(),
             graph: StableUnGraph::default(),
             root_node: None,
         };
@@ -62,10 +65,16 @@ impl VMGraphClip {
         for fg_idx in node_set {
             let mut edges = mapper.graph.get_graph().edges(*fg_idx).clone();
             while let Some(edge) = edges.next() {
-                if let Some(source_idx) = trans_map.get(&edge.source()) {
-                    if let Some(target_idx) = trans_map.get(&edge.target()) {
+                //Use the stored endpoints to keep the orientation of the original edge
+                let (source, target) = mapper.graph.get_graph().edge_endpoints(edge.id()).unwrap();
+                if let Some(source_idx) = trans_map.get(&source) {
+                    if let Some(target_idx) = trans_map.get(&target) {
                         if graph_clip.get_graph().contains_node(*source_idx) && graph_clip.get_graph().contains_node(*target_idx) {
-                            graph_clip.get_graph_mut().update_edge(*source_idx, *target_idx, EdgeData {user_data: 0});
+                            let edge_idx = edge.weight().user_data;
+                            graph_clip.get_graph_mut().update_edge(*source_idx, *target_idx, edge.weight().clone());
+                            if let Some(vm_edge) = mapper.edges.get(&edge_idx) {
+                                graph_clip.edges.insert(edge_idx, vm_edge.clone());
+                            }
                         }
                     }
                 }
@@ -105,6 +114,25 @@ impl VMGraphClip {
         return &mut self.nodes;
     }
 
+    //Adds an edge to the target VimMapper, carrying over the data of the clip edge it was copied from.
+    fn append_edge(&self, target: &mut VimMapper, n1: DefaultNodeIdx, n2: DefaultNodeIdx, clip_edge_data: &EdgeData<u32>) {
+        let new_edge_idx = target.add_edge(n1, n2);
+        let new_fg_edge = target.graph.get_graph().find_edge(n1, n2).unwrap();
+        target.graph.get_graph_mut()[new_fg_edge].length = clip_edge_data.length;
+        target.graph.get_graph_mut()[new_fg_edge].stiffness = clip_edge_data.stiffness;
+        if let Some(clip_edge) = self.edges.get(&clip_edge_data.user_data) {
+            //The nodes may already have been joined the other way around
+            let (source, _) = target.graph.get_graph().edge_endpoints(new_fg_edge).unwrap();
+            let mut direction = clip_edge.get_direction();
+            if source != n1 {
+                direction = direction.reversed();
+            }
+            let new_edge = target.edges.get_mut(&new_edge_idx).unwrap();
+            new_edge.set_label(clip_edge.get_label());
+            new_edge.set_direction(direction);
+        }
+    }
+
     pub fn append_node_clip(&self, target: &mut VimMapper, target_idx: Option<u32>, _register: String, pos: Option<Point>) {
         let mut trans_map: HashMap<DefaultNodeIdx, DefaultNodeIdx> = HashMap::new(); 
         if let Some(target_idx) = target_idx {
@@ -116,6 +144,7 @@ impl VMGraphClip {
                 target.nodes.get_mut(&target_idx).unwrap().set_label(
                     text
                 );
+                target.nodes.get_mut(&target_idx).unwrap().copy_metadata_from(self.nodes.get(&self.graph[self.root_node.unwrap()].data.user_data).unwrap());
                 target.nodes.get_mut(&target_idx).unwrap().load_input_text();
             }
             let target_node = &target.graph.get_graph()[target.nodes.get(&target_idx).unwrap().fg_index.unwrap()].clone();
@@ -140,6 +169,8 @@ impl VMGraphClip {
                         mass: node.data.mass,
                         repel_distance: node.data.repel_distance,
                         is_anchor: node.data.is_anchor,
+                        is_hidden: false,
+                        half_extent: node.data.half_extent,
                         user_data: new_index,
                     });
                     let mut vm_node = self.nodes.get(&node.data.user_data).unwrap().clone();
@@ -159,15 +190,16 @@ impl VMGraphClip {
                 let n1 = *trans_map.get(&edge.source()).unwrap();
                 let n2 = *trans_map.get(&edge.target()).unwrap();
                 tracing::debug!("Adding edge between {:?} and {:?}", n1, n2);
-                target.graph.add_edge(n1, n2, EdgeData { user_data: 0 });
+                self.append_edge(target, n1, n2, edge.weight());
             }
             if !replace_node {
-                target.graph.add_edge(target.nodes.get(&target_idx).unwrap().fg_index.unwrap(), *trans_map.get(&self.root_node.unwrap()).unwrap(), EdgeData{ user_data: 0 });
+                target.add_edge(target.nodes.get(&target_idx).unwrap().fg_index.unwrap(), *trans_map.get(&self.root_node.unwrap()).unwrap());
             }
             target.build_target_list_from_neighbors(target_idx);
         } else if let Some(root_node) = self.root_node {
             let external_node = target.add_external_node(self.nodes.get(&self.graph[root_node].data.user_data).unwrap().get_label().clone()).unwrap();
             let external_fg_index = target.get_nodes().get(&external_node).unwrap().fg_index.unwrap();
+            target.nodes.get_mut(&external_node).unwrap().copy_metadata_from(self.nodes.get(&self.graph[root_node].data.user_data).unwrap());
             target.set_node_as_active(external_node);
             if let Some(point) = pos {
                 target.graph.get_graph_mut()[external_fg_index].data.x = point.x;
@@ -184,6 +216,8 @@ impl VMGraphClip {
                         mass: node.data.mass,
                         repel_distance: node.data.repel_distance,
                         is_anchor: node.data.is_anchor,
+                        is_hidden: false,
+                        half_extent: node.data.half_extent,
                         user_data: new_index,
                     });
                     let mut vm_node = self.nodes.get(&node.data.user_data).unwrap().clone();
@@ -196,11 +230,11 @@ impl VMGraphClip {
             }
             for edge in self.graph.edge_references() {
                 if edge.source() == root_node {
-                    target.graph.add_edge(external_fg_index, *trans_map.get(&edge.target()).unwrap(), EdgeData { user_data: 0 });
+                    self.append_edge(target, external_fg_index, *trans_map.get(&edge.target()).unwrap(), edge.weight());
                 } else if edge.target() == root_node {
-                    target.graph.add_edge(*trans_map.get(&edge.source()).unwrap(), external_fg_index, EdgeData { user_data: 0 });
+                    self.append_edge(target, *trans_map.get(&edge.source()).unwrap(), external_fg_index, edge.weight());
                 } else {
-                    target.graph.add_edge(*trans_map.get(&edge.source()).unwrap(), *trans_map.get(&edge.target()).unwrap(), EdgeData { user_data: 0 });
+                    self.append_edge(target, *trans_map.get(&edge.source()).unwrap(), *trans_map.get(&edge.target()).unwrap(), edge.weight());
                 }
             }
             target.build_target_list_from_neighbors(external_node);
@@ -218,6 +252,7 @@ impl VMGraphClip {
             let label = self.nodes.get(&self.graph[root_node].data.user_data).unwrap().get_label().clone();
             // target.nodes.get_mut(&0).unwrap().label = label.clone();
             target.nodes.get_mut(&0).unwrap().set_label(label.clone());
+            target.nodes.get_mut(&0).unwrap().copy_metadata_from(self.nodes.get(&self.graph[root_node].data.user_data).unwrap());
             let new_root_node = 0;
             let new_root_fg_index = target.get_nodes().get(&0).unwrap().fg_index.unwrap();
             target.set_node_as_active(new_root_node);
@@ -232,6 +267,8 @@ impl VMGraphClip {
                         mass: node.data.mass,
                         repel_distance: node.data.repel_distance,
                         is_anchor: node.data.is_anchor,
+                        is_hidden: false,
+                        half_extent: node.data.half_extent,
                         user_data: new_index,
                     });
                     let mut vm_node = self.nodes.get(&node.data.user_data).unwrap().clone();
@@ -244,17 +281,17 @@ impl VMGraphClip {
             }
             for edge in self.graph.edge_references() {
                 if edge.source() == root_node {
-                    target.graph.add_edge(new_root_fg_index, *trans_map.get(&edge.target()).unwrap(), EdgeData { user_data: 0 });
+                    self.append_edge(&mut target, new_root_fg_index, *trans_map.get(&edge.target()).unwrap(), edge.weight());
                 } else if edge.target() == root_node {
-                    target.graph.add_edge(*trans_map.get(&edge.source()).unwrap(), new_root_fg_index, EdgeData { user_data: 0 });
+                    self.append_edge(&mut target, *trans_map.get(&edge.source()).unwrap(), new_root_fg_index, edge.weight());
                 } else {
-                    target.graph.add_edge(*trans_map.get(&edge.source()).unwrap(), *trans_map.get(&edge.target()).unwrap(), EdgeData { user_data: 0 });
+                    self.append_edge(&mut target, *trans_map.get(&edge.source()).unwrap(), *trans_map.get(&edge.target()).unwrap(), edge.weight());
                 }
             }
             target.build_target_list_from_neighbors(new_root_node);
             VMTab {
                 vm: WidgetPod::new(target),
-                tab_name: label.clone(),
+                tab_name: label.replace('\n', " "),
             }
     }
 }
\ No newline at end of file
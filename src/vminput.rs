@@ -27,12 +27,15 @@ pub enum Action {
     NullAction,
     CreateNewSheet,
     OpenExistingSheet,
+    OpenRecentSheet,
+    ReopenLastSession,
     SaveSheet,
     SaveSheetAs,
     SaveSheetAsOverwrite,
     QuitWithoutSaveGuard,
     QuitWithSaveGuard,
     SetSaveState,
+    ReloadSheetFromDisk,
     CycleNodeForward,
     CycleNodeBackward,
     CreateNewNode,
@@ -42,11 +45,37 @@ pub enum Action {
     IncreaseNodeMass,
     DecreaseNodeMass,
     ResetNodeMass,
+    LengthenTargetEdge,
+    ShortenTargetEdge,
+    ResetTargetEdgeLength,
     ToggleNodeAnchor,
     ActivateTargetedNode,
     EditActiveNodeSelectAll,
     EditActiveNodeAppend,
     EditActiveNodeInsert,
+    EditTargetEdge,
+    ToggleTargetEdgeArrow,
+    ToggleActiveEdgeArrow,
+    LinkTargetNode,
+    LinkMarkedNode,
+    UnlinkTargetNode,
+    ApplyNodeStyle,
+    OpenNodeMetadataInput,
+    SetNodeMetadata,
+    EditActiveNodeNote,
+    OpenNodeLinkInput,
+    SetNodeLink,
+    OpenNodeLink,
+    CopyNodeReference,
+    PasteNodeReference,
+    FollowNodeReference,
+    ToggleNodeFold,
+    OpenNodeFold,
+    CloseNodeFold,
+    CycleLayoutMode,
+    ToggleMinimap,
+    OpenSimulationParametersInput,
+    SetSimulationParameters,
     CutNode,
     CutNodeTree,
     AttemptNodeDeletion,
@@ -130,6 +159,8 @@ pub enum TextMotion {
     BeginningLine,
     EndLine,
     WholeLine,
+    ForwardLine,
+    BackwardLine,
 }
 
 #[allow(dead_code)]
@@ -180,6 +211,8 @@ bitflags! {
         const SearchedSheet =   0b001000000000;
         const SearchEntry =     0b010000000000;
         const Global =          0b100000000000;
+        const Link =           0b1000000000000;
+        const Style =         0b10000000000000;
     }
 }
 
@@ -303,6 +336,9 @@ pub struct VMInputManager {
     timeout_revert_token: Option<TimerToken>,
     timeout_revert_mode: Option<KeybindMode>,
     string_keybind_cache: Vec<Keybind>,
+    //Set while editing multi-line text such as a node note. Enter inserts a newline instead of accepting
+    // the text.
+    multiline: bool,
 }
 
 impl Default for VMInputManager {
@@ -328,6 +364,7 @@ impl Default for VMInputManager {
             timeout_revert_token: None,
             timeout_revert_mode: None,
             string_keybind_cache: vec![],
+            multiline: false,
             keybinds: vec![
                 Keybind { 
                     kb_type: KeybindType::Key,
@@ -589,6 +626,90 @@ impl Default for VMInputManager {
                     mode: (KeybindMode::Edit | KeybindMode::Sheet),
 					..Default::default()
                 },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("o"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ChangeMode,
+                            mode: Some(KeybindMode::Insert),
+                            ..Default::default()
+                    }),
+                    Some(
+                        ActionPayload {
+                            action: Action::ExecuteTextAction,
+                            text_action: Some(TextAction { 
+                                operation: TextOperation::None, 
+                                outer_count: None, 
+                                inner_count: None, 
+                                text_obj: None, 
+                                text_motion: Some(TextMotion::EndLine), 
+                                character_string: None,
+                            }),
+                            ..Default::default()
+                        }
+                    ),
+                    Some(
+                        ActionPayload {
+                            action: Action::InsertCharacter,
+                            string: Some(String::from("\n")),
+                            ..Default::default()
+                        }
+                    ),
+                    ],
+                    mode: KeybindMode::Edit,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("O"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ChangeMode,
+                            mode: Some(KeybindMode::Insert),
+                            ..Default::default()
+                    }),
+                    Some(
+                        ActionPayload {
+                            action: Action::ExecuteTextAction,
+                            text_action: Some(TextAction { 
+                                operation: TextOperation::None, 
+                                outer_count: None, 
+                                inner_count: None, 
+                                text_obj: None, 
+                                text_motion: Some(TextMotion::BeginningLine), 
+                                character_string: None,
+                            }),
+                            ..Default::default()
+                        }
+                    ),
+                    Some(
+                        ActionPayload {
+                            action: Action::InsertCharacter,
+                            string: Some(String::from("\n")),
+                            ..Default::default()
+                        }
+                    ),
+                    Some(
+                        ActionPayload {
+                            action: Action::ExecuteTextAction,
+                            text_action: Some(TextAction { 
+                                operation: TextOperation::None, 
+                                outer_count: None, 
+                                inner_count: None, 
+                                text_obj: None, 
+                                text_motion: Some(TextMotion::BackwardCharacter), 
+                                character_string: None,
+                            }),
+                            ..Default::default()
+                        }
+                    ),
+                    ],
+                    mode: KeybindMode::Edit,
+					..Default::default()
+                },
                 Keybind { 
                     kb_type: KeybindType::Key, 
                     key: Some(Key::Character(String::from("I"))),
@@ -880,6 +1001,98 @@ impl Default for VMInputManager {
                     mode: KeybindMode::Sheet,
 					..Default::default()
                 },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("s"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ChangeMode,
+                            mode: Some(KeybindMode::Style),
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("R"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::PasteNodeReference,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("t"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::EditActiveNodeNote,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("#"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::OpenNodeMetadataInput,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from(":"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::OpenSimulationParametersInput,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("&"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ChangeModeWithTimeoutRevert,
+                            mode: Some(KeybindMode::Link),
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Enter),
+                    modifiers: Some(Modifiers::SHIFT), 
+                    action_payloads: vec![
+                        Some(ActionPayload {
+                            action: Action::LinkTargetNode,
+                            ..Default::default()
+                        }),
+                        Some(ActionPayload {
+                            action: Action::ChangeMode,
+                            mode: Some(KeybindMode::Sheet),
+                            ..Default::default()
+                        }),
+                    ],
+                    mode: KeybindMode::SearchedSheet,
+					..Default::default()
+                },
                 Keybind { 
                     kb_type: KeybindType::Key, 
                     key: Some(Key::Character(String::from("/"))),
@@ -950,6 +1163,42 @@ impl Default for VMInputManager {
                     mode: KeybindMode::Sheet,
 					..Default::default()
                 },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("]"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::LengthenTargetEdge,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("["))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ShortenTargetEdge,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("\\"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ResetTargetEdgeLength,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
                 Keybind { 
                     kb_type: KeybindType::Key, 
                     key: Some(Key::Character(String::from("@"))),
@@ -962,6 +1211,54 @@ impl Default for VMInputManager {
                     mode: KeybindMode::Sheet,
 					..Default::default()
                 },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("e"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::EditTargetEdge,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from(">"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ToggleTargetEdgeArrow,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("<"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::ToggleActiveEdgeArrow,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::Key, 
+                    key: Some(Key::Character(String::from("X"))),
+                    modifiers: None, 
+                    action_payloads: vec![Some(
+                        ActionPayload {
+                            action: Action::UnlinkTargetNode,
+                            ..Default::default()
+                    })],
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
                 Keybind { 
                     kb_type: KeybindType::Key, 
                     key: Some(Key::Character(String::from("`"))),
@@ -1242,6 +1539,27 @@ impl Default for VMInputManager {
                     next: Some(BuildState::Complete),
 					..Default::default()
                 },
+                //Doubled operators (dd, cc) act on the whole current line
+                Keybind {
+                    kb_type: KeybindType::String,
+                    string: Some("d".to_string()),
+                    motion: Some(TextMotion::WholeLine),
+                    accepts_outer_count: Some(false),
+                    accepts_inner_count: Some(false),
+                    next: Some(BuildState::Complete),
+                    mode: KeybindMode::Edit,
+                    ..Default::default()
+                },
+                Keybind {
+                    kb_type: KeybindType::String,
+                    string: Some("c".to_string()),
+                    motion: Some(TextMotion::WholeLine),
+                    accepts_outer_count: Some(false),
+                    accepts_inner_count: Some(false),
+                    next: Some(BuildState::Complete),
+                    mode: KeybindMode::Edit,
+                    ..Default::default()
+                },
                 Keybind {
                     kb_type: KeybindType::String,
                     string: Some("iw".to_string()),
@@ -1357,6 +1675,28 @@ impl Default for VMInputManager {
                     mode: KeybindMode::Edit,
                     ..Default::default()
                 },
+                Keybind {
+                    kb_type: KeybindType::String,
+                    string: Some("j".to_string()),
+                    operation: Some(TextOperation::None),
+                    motion: Some(TextMotion::ForwardLine),
+                    accepts_outer_count: Some(true),
+                    accepts_inner_count: Some(false),
+                    next: Some(BuildState::Complete),
+                    mode: KeybindMode::Edit,
+                    ..Default::default()
+                },
+                Keybind {
+                    kb_type: KeybindType::String,
+                    string: Some("k".to_string()),
+                    operation: Some(TextOperation::None),
+                    motion: Some(TextMotion::BackwardLine),
+                    accepts_outer_count: Some(true),
+                    accepts_inner_count: Some(false),
+                    next: Some(BuildState::Complete),
+                    mode: KeybindMode::Edit,
+                    ..Default::default()
+                },
                 Keybind {
                     kb_type: KeybindType::String,
                     string: Some("w".to_string()),
@@ -1455,7 +1795,52 @@ impl Default for VMInputManager {
                                     ..Default::default()
                                 }
                             )]
-                        }
+                        },
+                        Subcommand {
+                            string: "x".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::OpenNodeLink,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
+                        Subcommand {
+                            string: "u".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::OpenNodeLinkInput,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
+                        Subcommand {
+                            string: "d".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::FollowNodeReference,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
+                        Subcommand {
+                            string: "l".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::CycleLayoutMode,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
+                        Subcommand {
+                            string: "m".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::ToggleMinimap,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
                     ]),
                     next: Some(BuildState::AwaitSubcommand),
                     mode: KeybindMode::Sheet,
@@ -1490,6 +1875,52 @@ impl Default for VMInputManager {
                                 }
                             )]
                         },
+                        Subcommand {
+                            string: "r".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::CopyNodeReference,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
+                    ]),
+                    next: Some(BuildState::AwaitSubcommand),
+                    mode: KeybindMode::Sheet,
+					..Default::default()
+                },
+                Keybind { 
+                    kb_type: KeybindType::String, 
+                    string: Some("z".to_string()),
+                    operation: Some(TextOperation::None),
+                    subcommands: Some(vec![
+                        Subcommand {
+                            string: "a".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::ToggleNodeFold,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
+                        Subcommand {
+                            string: "o".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::OpenNodeFold,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
+                        Subcommand {
+                            string: "c".to_string(),
+                            action_payloads: vec![Some(
+                                ActionPayload {
+                                    action: Action::CloseNodeFold,
+                                    ..Default::default()
+                                }
+                            )]
+                        },
                     ]),
                     next: Some(BuildState::AwaitSubcommand),
                     mode: KeybindMode::Sheet,
@@ -2057,6 +2488,74 @@ impl VMInputManager {
                     ];
                 }
             },
+            KeybindMode::Link => {
+                if let Key::Character(character) = key_event.key {
+                    if key_event.mods.alt() || key_event.mods.ctrl() {
+                        self.set_new_revert_timeout(ctx);
+                        return vec![None];
+                    }
+                    self.clear_build();
+                    self.clear_revert_timeout();
+                    return vec![
+                        Some(ActionPayload {
+                            action: Action::ChangeMode,
+                            mode: Some(KeybindMode::Sheet),
+                            ..Default::default()
+                        }),
+                        Some(ActionPayload {
+                            action: Action::LinkMarkedNode,
+                            string: Some(character),
+                            ..Default::default()
+                    })];
+                } else {
+                    self.clear_build();
+                    self.clear_revert_timeout();
+                    return vec![
+                        Some(
+                            ActionPayload {
+                                action: Action::ChangeMode,
+                                mode: Some(KeybindMode::Sheet),
+                                ..Default::default()
+                            }
+                        )
+                    ];
+                }
+            },
+            KeybindMode::Style => {
+                //The style picker stays open until a style is chosen or it's dismissed
+                if let Key::Character(character) = key_event.key {
+                    if !(key_event.mods == RawMods::None || key_event.mods == RawMods::Shift) {
+                        return vec![None];
+                    }
+                    return vec![
+                        Some(
+                            ActionPayload {
+                                action: Action::ChangeMode,
+                                mode: Some(KeybindMode::Sheet),
+                                ..Default::default()
+                            }
+                        ),
+                        Some(
+                            ActionPayload {
+                                action: Action::ApplyNodeStyle,
+                                string: Some(character),
+                                ..Default::default()
+                            }
+                        )
+                    ];
+                } else if key_event.key == Key::Escape {
+                    return vec![
+                        Some(
+                            ActionPayload {
+                                action: Action::ChangeMode,
+                                mode: Some(KeybindMode::Sheet),
+                                ..Default::default()
+                            }
+                        ),
+                    ];
+                }
+                return vec![None];
+            },
             KeybindMode::Mark => {
                 if let Key::Character(character) = key_event.key {
                     if !(key_event.mods == RawMods::None || key_event.mods == RawMods::Shift) {
@@ -2170,6 +2669,19 @@ impl VMInputManager {
                     },
                     Key::Enter => {
                     self.clear_build();
+                    if self.multiline || key_event.mods.shift() {
+                        return vec![
+                            Some(ActionPayload {
+                                action: Action::ConfirmInserts,
+                                ..Default::default()
+                            }),
+                            Some(ActionPayload {
+                                action: Action::InsertCharacter,
+                                string: Some(String::from("\n")),
+                                ..Default::default()
+                            })
+                        ];
+                    }
                     return vec![Some(ActionPayload {
                         action: Action::AcceptNodeText,
                         ..Default::default() 
@@ -2507,6 +3019,10 @@ impl VMInputManager {
         return self.mode.clone();
     }
 
+    pub fn set_multiline(&mut self, multiline: bool) {
+        self.multiline = multiline;
+    }
+
     pub fn set_keybind_mode(&mut self, mode: KeybindMode) {
         match mode {
             KeybindMode::Start => {
@@ -2542,6 +3058,16 @@ impl VMInputManager {
                 self.input_string = String::from("");
                 self.mode_prompt = String::from("'");
             },
+            KeybindMode::Link => {
+                self.mode_label = String::from("<link>");
+                self.input_string = String::from("");
+                self.mode_prompt = String::from("&");
+            },
+            KeybindMode::Style => {
+                self.mode_label = String::from("<style>");
+                self.input_string = String::from("");
+                self.mode_prompt = String::from("s");
+            },
             KeybindMode::Mark => {
                 self.mode_label = String::from("<mark>");
                 self.input_string = String::from("");
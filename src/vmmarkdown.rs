@@ -0,0 +1,192 @@
+// Copyright 2022 Doug Powers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::Range;
+
+use druid::Color;
+use druid::piet::{PietTextLayoutBuilder, TextLayoutBuilder, TextAttribute, FontFamily, FontStyle, FontWeight};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InlineStyle {
+    Bold,
+    Italic,
+    Code,
+    Link,
+    //Characters matched by a search. Never produced by parse_inline_markdown.
+    Highlight(Color),
+}
+
+//A styled run of the rendered label. The range is a byte range into the text returned by
+// parse_inline_markdown, not into the raw label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineSpan {
+    pub range: Range<usize>,
+    pub style: InlineStyle,
+}
+
+//Parses the lightweight inline markup allowed in labels: **bold**, _italic_, `code`, and [text](url).
+// Returns the label with the markup removed and the spans to style. Unclosed markup is left as-is, and
+// a backslash escapes the character after it.
+pub fn parse_inline_markdown(text: &str) -> (String, Vec<InlineSpan>) {
+    let mut rendered = String::new();
+    let mut spans: Vec<InlineSpan> = vec![];
+    parse_into(text, &mut rendered, &mut spans);
+    return (rendered, spans);
+}
+
+fn parse_into(text: &str, rendered: &mut String, spans: &mut Vec<InlineSpan>) {
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap();
+        if c == '\\' {
+            if let Some(escaped) = rest[1..].chars().next() {
+                rendered.push(escaped);
+                i += 1 + escaped.len_utf8();
+                continue;
+            }
+        } else if c == '`' {
+            //Code spans are literal so markup inside them isn't parsed
+            if let Some(end) = rest[1..].find('`') {
+                if end > 0 {
+                    let start = rendered.len();
+                    rendered.push_str(&rest[1..end+1]);
+                    spans.push(InlineSpan { range: start..rendered.len(), style: InlineStyle::Code });
+                    i += end + 2;
+                    continue;
+                }
+            }
+        } else if rest.starts_with("**") {
+            if let Some(end) = rest[2..].find("**") {
+                if end > 0 {
+                    push_nested(&rest[2..end+2], InlineStyle::Bold, rendered, spans);
+                    i += end + 4;
+                    continue;
+                }
+            }
+        } else if c == '_' && is_boundary_before(&text[..i]) {
+            //Underscores inside words (snake_case) aren't treated as markup
+            if let Some(end) = find_closing_underscore(&rest[1..]) {
+                push_nested(&rest[1..end+1], InlineStyle::Italic, rendered, spans);
+                i += end + 2;
+                continue;
+            }
+        } else if c == '[' {
+            if let Some(text_end) = rest.find("](") {
+                if let Some(url_end) = rest[text_end+2..].find(')') {
+                    if text_end > 1 && url_end > 0 {
+                        push_nested(&rest[1..text_end], InlineStyle::Link, rendered, spans);
+                        i += text_end + 2 + url_end + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        rendered.push(c);
+        i += c.len_utf8();
+    }
+}
+
+fn push_nested(inner: &str, style: InlineStyle, rendered: &mut String, spans: &mut Vec<InlineSpan>) {
+    let start = rendered.len();
+    parse_into(inner, rendered, spans);
+    spans.push(InlineSpan { range: start..rendered.len(), style });
+}
+
+fn is_boundary_before(before: &str) -> bool {
+    return before.chars().last().map_or(true, |c| !c.is_alphanumeric());
+}
+
+//Finds the closing underscore of an italic run, which must not be followed by a letter or digit.
+fn find_closing_underscore(text: &str) -> Option<usize> {
+    for (idx, _) in text.match_indices('_') {
+        if idx == 0 {
+            continue;
+        }
+        let after = text[idx+1..].chars().next();
+        if after.map_or(true, |c| !c.is_alphanumeric()) {
+            return Some(idx);
+        }
+    }
+    return None;
+}
+
+//Applies the parsed spans to a text layout builder as piet range attributes.
+pub fn apply_inline_spans(mut builder: PietTextLayoutBuilder, spans: &[InlineSpan]) -> PietTextLayoutBuilder {
+    for span in spans {
+        builder = match span.style {
+            InlineStyle::Bold => builder.range_attribute(span.range.clone(), TextAttribute::Weight(FontWeight::BOLD)),
+            InlineStyle::Italic => builder.range_attribute(span.range.clone(), TextAttribute::Style(FontStyle::Italic)),
+            InlineStyle::Code => builder.range_attribute(span.range.clone(), TextAttribute::FontFamily(FontFamily::MONOSPACE)),
+            InlineStyle::Link => builder.range_attribute(span.range.clone(), TextAttribute::Underline(true)),
+            InlineStyle::Highlight(color) => builder
+                .range_attribute(span.range.clone(), TextAttribute::TextColor(color))
+                .range_attribute(span.range.clone(), TextAttribute::Weight(FontWeight::BOLD)),
+        };
+    }
+    return builder;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn span(range: Range<usize>, style: InlineStyle) -> InlineSpan {
+        return InlineSpan { range, style };
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(parse_inline_markdown("plain label"), (String::from("plain label"), vec![]));
+        assert_eq!(parse_inline_markdown(""), (String::new(), vec![]));
+    }
+
+    #[test]
+    fn test_nested_markers() {
+        assert_eq!(parse_inline_markdown("**bold _it_**"), (String::from("bold it"), vec![
+            span(5..7, InlineStyle::Italic),
+            span(0..7, InlineStyle::Bold),
+        ]));
+        assert_eq!(parse_inline_markdown("see [**docs**](https://example.com)"), (String::from("see docs"), vec![
+            span(4..8, InlineStyle::Bold),
+            span(4..8, InlineStyle::Link),
+        ]));
+        //Markup inside code spans is kept as written
+        assert_eq!(parse_inline_markdown("`**x**`"), (String::from("**x**"), vec![span(0..5, InlineStyle::Code)]));
+    }
+
+    #[test]
+    fn test_unclosed_markers() {
+        for text in ["**bold", "_italic", "`code", "[text](url", "[text]", "****", "a ** b", "snake_case_name"] {
+            assert_eq!(parse_inline_markdown(text), (String::from(text), vec![]));
+        }
+        assert_eq!(parse_inline_markdown("**open _it_"), (String::from("**open it"), vec![span(7..9, InlineStyle::Italic)]));
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(parse_inline_markdown("\\**not bold**"), (String::from("**not bold**"), vec![]));
+        assert_eq!(parse_inline_markdown("\\_x_ \\é"), (String::from("_x_ é"), vec![]));
+        assert_eq!(parse_inline_markdown("trailing \\"), (String::from("trailing \\"), vec![]));
+    }
+
+    #[test]
+    fn test_multi_byte_text() {
+        assert_eq!(parse_inline_markdown("**café** ünï"), (String::from("café ünï"), vec![span(0..5, InlineStyle::Bold)]));
+        assert_eq!(parse_inline_markdown("→ `ß`"), (String::from("→ ß"), vec![span(4..6, InlineStyle::Code)]));
+        assert_eq!(parse_inline_markdown("_日本_ 語"), (String::from("日本 語"), vec![span(0..6, InlineStyle::Italic)]));
+        //Underscores between letters of any script are part of the word
+        assert_eq!(parse_inline_markdown("日_本_語"), (String::from("日_本_語"), vec![]));
+    }
+}
@@ -14,9 +14,10 @@
 
 use druid::{Vec2, piet::{PietTextLayout, TextLayout, Text, TextLayoutBuilder}, Rect, PaintCtx, RenderContext, Affine, kurbo::TranslateScale, Point, FontFamily, FontWeight, Color};
 use serde::{Serialize, Deserialize};
+use std::collections::{BTreeSet, BTreeMap};
 use vm_force_graph_rs::{DefaultNodeIdx, ForceGraph};
 
-use crate::{constants::*, vmconfig::*, vmtextinput::VMTextInput};
+use crate::{constants::*, vmconfig::*, vmtextinput::VMTextInput, vmstyle::VMNodeStyle};
 
 //Position on the node to paint a badge. Format YposXpos. Only corners are guaranteed to have space 
 // on the layout
@@ -32,6 +33,46 @@ pub enum BadgePosition {
     CenterLeft,
 }
 
+impl BadgePosition {
+    //Returns the point on the given (already inflated) border that a badge at this position is centered on.
+    pub fn get_point(&self, border: &Rect) -> Vec2 {
+        match self {
+            BadgePosition::TopLeft => {
+                return border.origin().to_vec2();
+            }
+            BadgePosition::TopCenter => {
+                return border.origin().to_vec2() + Vec2::new(border.width()/2., 0.);
+            }
+            BadgePosition::TopRight => {
+                return border.origin().to_vec2() + Vec2::new(border.width(), 0.);
+            }
+            BadgePosition::CenterRight => {
+                return border.origin().to_vec2() + 
+                Vec2::new(border.width(), 0.) +
+                Vec2::new(0., border.height()/2.);
+            }
+            BadgePosition::BottomRight => {
+                return border.origin().to_vec2() + 
+                Vec2::new(border.width(), 0.) +
+                Vec2::new(0., border.height());
+            }
+            BadgePosition::BottomCenter => {
+                return border.origin().to_vec2() + 
+                Vec2::new(border.width()/2., 0.) +
+                Vec2::new(0., border.height());
+            }
+            BadgePosition::BottomLeft => {
+                return border.origin().to_vec2() + 
+                Vec2::new(0., border.height());
+            }
+            BadgePosition::CenterLeft => {
+                return border.origin().to_vec2() + 
+                Vec2::new(0., border.height()/2.);
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(remote = "Rect")]
 struct RectDef {
@@ -41,6 +82,17 @@ struct RectDef {
     y1: f64
 }
 
+//Points at a node in another tab of the same sheet. The tab is found by name and the node by its index,
+// which doesn't change for the life of the node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VMNodeReference {
+    pub tab_name: String,
+    pub node_index: u32,
+    //Set when the referenced tab or node is found to be missing
+    #[serde(default)]
+    pub broken: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VMNode {
     label: String,
@@ -52,6 +104,27 @@ pub struct VMNode {
     // pub text_cursor_index: usize,
     #[serde(skip)]
     pub text_input: VMTextInput,
+    #[serde(default)]
+    pub style: VMNodeStyle,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+    #[serde(default)]
+    note: Option<String>,
+    //A URL, file path, or sheet path (optionally followed by #<mark>) opened with gx
+    #[serde(default)]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub reference: Option<VMNodeReference>,
+    //A folded node hides its descendants from the simulation and the sheet
+    #[serde(default)]
+    pub folded: bool,
+    //Number of descendants hidden by the fold. Recomputed by VimMapper::refresh_folds.
+    #[serde(skip)]
+    pub hidden_count: usize,
+    #[serde(skip)]
+    pub note_input: VMTextInput,
     //Cached rect of the node, transformed to screen coords. Used to scroll node into view.
     #[serde(with = "RectDef")]
     pub node_rect: Rect,
@@ -67,6 +140,15 @@ impl Default for VMNode {
             is_active: false,
             mark: None,
             text_input: VMTextInput::new(label, None),
+            style: VMNodeStyle::default(),
+            tags: BTreeSet::new(),
+            properties: BTreeMap::new(),
+            note: None,
+            link: None,
+            reference: None,
+            folded: false,
+            hidden_count: 0,
+            note_input: VMTextInput::new(String::new(), None),
             // text_cursor_index: 0,
             node_rect: Rect::new(0.,0.,0.,0.),
         };
@@ -84,6 +166,15 @@ impl VMNode {
             is_active: false,
             mark: None,
             text_input: VMTextInput::new(label, None),
+            style: VMNodeStyle::default(),
+            tags: BTreeSet::new(),
+            properties: BTreeMap::new(),
+            note: None,
+            link: None,
+            reference: None,
+            folded: false,
+            hidden_count: 0,
+            note_input: VMTextInput::new(String::new(), None),
             // text_cursor_index: 0,
             node_rect: Rect::new(0.,0.,0.,0.),
         };
@@ -129,6 +220,128 @@ impl VMNode {
         self.text_input.set_text(self.label.clone());
     }
 
+    pub fn set_note(&mut self, note: Option<String>) {
+        self.note = note;
+    }
+
+    pub fn get_note(&self) -> Option<String> {
+        self.note.clone()
+    }
+
+    //Copies everything a node carries besides its label and its place in the graph. Used when a node is
+    // pasted into a node that already exists, so that new fields only need to be added here.
+    pub fn copy_metadata_from(&mut self, other: &VMNode) {
+        self.style = other.style.clone();
+        self.tags = other.tags.clone();
+        self.properties = other.properties.clone();
+        self.note = other.note.clone();
+        self.link = other.link.clone();
+        self.reference = other.reference.clone();
+        self.folded = other.folded;
+    }
+
+    //Empty notes are stored as None so the note badge is only drawn for nodes with a note.
+    fn note_from_input(&mut self) {
+        let text = self.note_input.get_text();
+        if text.trim().is_empty() {
+            self.note = None;
+        } else {
+            self.note = Some(text);
+        }
+    }
+
+    pub fn save_note_text(&mut self) {
+        self.note_from_input();
+        self.note_input.push_history();
+    }
+
+    pub fn undo_note(&mut self) {
+        self.note_input.undo();
+        self.note_from_input();
+    }
+
+    pub fn redo_note(&mut self) {
+        self.note_input.redo();
+        self.note_from_input();
+    }
+
+    //Starts a fresh note input so undo history doesn't reach back past the start of this edit.
+    pub fn load_note_input_text(&mut self) {
+        self.note_input = VMTextInput::new(self.note.clone().unwrap_or_default(), None);
+    }
+
+    //Formats the node's tags and properties as they're entered in the metadata input, e.g.
+    // `#urgent #backend owner:alice`. Values containing whitespace are quoted.
+    pub fn get_metadata_string(&self) -> String {
+        let mut tokens: Vec<String> = self.tags.iter().map(|tag| format!("#{}", tag)).collect();
+        for (key, value) in &self.properties {
+            if value.contains(char::is_whitespace) {
+                tokens.push(format!("{}:\"{}\"", key, value));
+            } else {
+                tokens.push(format!("{}:{}", key, value));
+            }
+        }
+        return tokens.join(" ");
+    }
+
+    //Replaces the node's tags and properties with those parsed from a metadata string. Tokens of the
+    // form key:value become properties and all others become tags, with or without a leading #.
+    pub fn set_metadata_from_string(&mut self, text: &str) {
+        self.tags.clear();
+        self.properties.clear();
+        for token in VMNode::split_metadata_tokens(text) {
+            if let Some((key, value)) = token.split_once(':') {
+                if !key.is_empty() && !key.starts_with('#') {
+                    self.properties.insert(key.to_string(), value.trim_matches('"').to_string());
+                    continue;
+                }
+            }
+            let tag = token.trim_start_matches('#');
+            if !tag.is_empty() {
+                self.tags.insert(tag.to_string());
+            }
+        }
+    }
+
+    //Splits on whitespace except inside double quotes. The quotes are kept in the tokens.
+    pub fn split_metadata_tokens(text: &str) -> Vec<String> {
+        let mut tokens: Vec<String> = vec![];
+        let mut current = String::new();
+        let mut quoted = false;
+        for c in text.chars() {
+            if c == '"' {
+                quoted = !quoted;
+                current.push(c);
+            } else if c.is_whitespace() && !quoted {
+                if !current.is_empty() {
+                    tokens.push(current.clone());
+                    current.clear();
+                }
+            } else {
+                current.push(c);
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+        return tokens;
+    }
+
+    //Whether the node has a tag beginning with the given text (case-insensitive).
+    pub fn has_tag_prefix(&self, prefix: &str) -> bool {
+        let prefix = prefix.to_lowercase();
+        return self.tags.iter().any(|tag| tag.to_lowercase().starts_with(&prefix));
+    }
+
+    //Whether the node has a property with the given key whose value contains the given text
+    // (both case-insensitive).
+    pub fn has_property_match(&self, key: &str, value: &str) -> bool {
+        let value = value.to_lowercase();
+        return self.properties.iter().any(|(k, v)| {
+            k.to_lowercase() == key.to_lowercase() && v.to_lowercase().contains(&value)
+        });
+    }
+
     pub fn paint_node(
         &mut self, 
         ctx: &mut PaintCtx, 
@@ -163,12 +376,12 @@ impl VMNode {
             ctx.transform(Affine::from(TranslateScale::new(-1.0*(label_size.to_vec2())/2.0, 1.0)));
             ctx.transform(Affine::from(TranslateScale::new(pos, 1.0)));
             let rect = label_size.to_rect().inflate(DEFAULT_BORDER_WIDTH, DEFAULT_BORDER_WIDTH);
-            let border = druid::piet::kurbo::RoundedRect::from_rect(rect, DEFAULT_BORDER_RADIUS);
+            let border = druid::piet::kurbo::RoundedRect::from_rect(rect, self.style.get_border_radius(&rect));
             //Cache this node's screen space-transformed rect (only if not drawn as a list member)
             if set_rect {
                 self.node_rect = ctx.current_transform().transform_rect_bbox(rect).clone();
             }
-            let mut border_color = config.get_color(VMColor::NodeBorderColor).ok().expect("node border color not found in config");
+            let mut border_color = self.style.get_border_color().unwrap_or(config.get_color(VMColor::NodeBorderColor).ok().expect("node border color not found in config"));
             let mut border_width = DEFAULT_BORDER_WIDTH;
             if self.is_active {
                 border_color = config.get_color(VMColor::ActiveNodeBorderColor).ok().expect("active node border color not found in config");
@@ -182,9 +395,13 @@ impl VMNode {
 
             let border_background;
             if !enabled {
-                border_background = config.get_color(VMColor::DisabledNodeBackgroundColor).ok().expect("DIsabled node background color not found in config");
+                if let Some(fill) = self.style.get_fill_color() {
+                    border_background = fill.with_alpha(0.5);
+                } else {
+                    border_background = config.get_color(VMColor::DisabledNodeBackgroundColor).ok().expect("DIsabled node background color not found in config");
+                }
             } else {
-                border_background = config.get_color(VMColor::NodeBackgroundColor).ok().expect("Node background color not found in config");
+                border_background = self.style.get_fill_color().unwrap_or(config.get_color(VMColor::NodeBackgroundColor).ok().expect("Node background color not found in config"));
             }
 
             let badge_border_color = border_color.clone();
@@ -209,6 +426,31 @@ impl VMNode {
                 self.paint_node_badge(ctx, z_index, graph, enabled, config, &"⚓".to_string(), BadgePosition::BottomLeft, &rect, &badge_border_color)
             }
 
+            if let Some(reference) = &self.reference {
+                let badge = if reference.broken { "⚠" } else { "↪" };
+                self.paint_node_badge(ctx, z_index, graph, enabled, config, &badge.to_string(), BadgePosition::CenterLeft, &rect, &badge_border_color);
+            }
+
+            if self.folded && self.hidden_count > 0 {
+                self.paint_node_badge(ctx, z_index, graph, enabled, config, &self.hidden_count.to_string(), BadgePosition::CenterRight, &rect, &badge_border_color);
+            }
+
+            if self.link.is_some() {
+                self.paint_node_badge(ctx, z_index, graph, enabled, config, &"🔗".to_string(), BadgePosition::TopLeft, &rect, &badge_border_color);
+            }
+
+            if self.note.is_some() {
+                self.paint_node_badge(ctx, z_index, graph, enabled, config, &"≡".to_string(), BadgePosition::BottomRight, &rect, &badge_border_color);
+            }
+
+            if !self.tags.is_empty() {
+                let mut chip_text = self.tags.iter().take(MAX_TAGS_IN_CHIP).map(|tag| format!("#{}", tag)).collect::<Vec<String>>().join(" ");
+                if self.tags.len() > MAX_TAGS_IN_CHIP {
+                    chip_text += &format!(" +{}", self.tags.len() - MAX_TAGS_IN_CHIP);
+                }
+                self.paint_node_chip(ctx, enabled, config, &chip_text, BadgePosition::TopCenter, &rect, &badge_border_color);
+            }
+
             //Paint debug decals (node index)
             if debug_data {
                 ctx.transform(Affine::from(TranslateScale::new(Vec2::new(-10., -10.), 1.)));
@@ -245,41 +487,7 @@ impl VMNode {
             BADGE_BORDER_INFLATION_AMOUNT,
             BADGE_BORDER_INFLATION_AMOUNT,
         );
-        let mark_point: Vec2;
-        match position {
-            BadgePosition::TopLeft => {
-                mark_point = border.origin().to_vec2();
-            }
-            BadgePosition::TopCenter => {
-                mark_point = border.origin().to_vec2() + Vec2::new(border.width()/2., 0.);
-            }
-            BadgePosition::TopRight => {
-                mark_point = border.origin().to_vec2() + Vec2::new(border.width(), 0.);
-            }
-            BadgePosition::CenterRight => {
-                mark_point = border.origin().to_vec2() + 
-                Vec2::new(border.width(), 0.) +
-                Vec2::new(0., border.height()/2.);
-            }
-            BadgePosition::BottomRight => {
-                mark_point = border.origin().to_vec2() + 
-                Vec2::new(border.width(), 0.) +
-                Vec2::new(0., border.height());
-            }
-            BadgePosition::BottomCenter => {
-                mark_point = border.origin().to_vec2() + 
-                Vec2::new(border.width()/2., 0.) +
-                Vec2::new(0., border.height());
-            }
-            BadgePosition::BottomLeft => {
-                mark_point = border.origin().to_vec2() + 
-                Vec2::new(0., border.height());
-            }
-            BadgePosition::CenterLeft => {
-                mark_point = border.origin().to_vec2() + 
-                Vec2::new(0., border.height()/2.);
-            }
-        }
+        let mark_point = position.get_point(&border);
 
         let layout = ctx.text()
         .new_text_layout(character.clone())
@@ -311,4 +519,47 @@ impl VMNode {
             // });
         });
     }
-}
\ No newline at end of file
+
+    //Paints a pill-shaped chip of text centered on a given BadgePosition. Used for text too long for a
+    // round badge, such as tags.
+    // NOTE: Expects to be executed within a context transformation to node-local position
+    pub fn paint_node_chip(&mut self,
+         ctx: &mut PaintCtx,
+         enabled: bool,
+         config: &VMConfigVersion4,
+         text: &String,
+         position: BadgePosition,
+         border: &Rect,
+         border_color: &Color,
+    ) {
+        let border = border.inflate(
+            BADGE_BORDER_INFLATION_AMOUNT,
+            BADGE_BORDER_INFLATION_AMOUNT,
+        );
+        let chip_point = position.get_point(&border);
+
+        let layout = ctx.text()
+        .new_text_layout(text.clone())
+        .font(FontFamily::SANS_SERIF, 11.)
+        .text_color(
+            if enabled {
+                config.get_color(VMColor::LabelTextColor).ok().expect("label text color not found in config")
+            } else {
+                config.get_color(VMColor::DisabledLabelTextColor).ok().expect("label text color not found in config")
+            })
+        .build().unwrap();
+        ctx.with_save(move |ctx| {
+            let size = layout.size();
+            let chip_rect = Rect::from_center_size(chip_point.to_point(), size).inflate(size.height/2., 1.);
+            let chip = chip_rect.to_rounded_rect(chip_rect.height()/2.);
+            let background_color = if enabled {
+                config.get_color(VMColor::NodeBackgroundColor).ok().expect("chip background color not found in config")
+            } else {
+                config.get_color(VMColor::DisabledNodeBackgroundColor).ok().expect("chip background color not found in config")
+            };
+            ctx.fill(chip, &background_color);
+            ctx.stroke(chip, border_color, DEFAULT_MARK_BORDER_WIDTH);
+            ctx.draw_text(&layout, chip_point.to_point() - size.to_vec2()/2.);
+        });
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2022 Doug Powers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, path::PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::constants::{DEFAULT_CONFIG_DIR_NAME, DEFAULT_RECENT_FILE_NAME, CURRENT_RECENT_FILE_VERSION, MAX_RECENT_FILES};
+
+//The sheet that was open when Vim-Mapper last quit along with the viewport of its active tab.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VMLastSession {
+    pub path: PathBuf,
+    pub active_tab: usize,
+    pub zoom_index: usize,
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+//Most recently used sheets, newest first. Kept separate from the config file as it's rewritten
+// every time a sheet is opened or saved.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VMRecentVersion1 {
+    pub file_version: String,
+    pub files: Vec<PathBuf>,
+    pub last_session: Option<VMLastSession>,
+}
+
+impl Default for VMRecentVersion1 {
+    fn default() -> Self {
+        VMRecentVersion1 {
+            file_version: String::from(CURRENT_RECENT_FILE_VERSION),
+            files: vec![],
+            last_session: None,
+        }
+    }
+}
+
+impl VMRecentVersion1 {
+    pub fn add_file(&mut self, path: PathBuf) {
+        self.files.retain(|v| *v != path);
+        self.files.insert(0, path);
+        self.files.truncate(MAX_RECENT_FILES);
+    }
+
+    pub fn remove_file(&mut self, path: &PathBuf) {
+        self.files.retain(|v| v != path);
+        if let Some(session) = &self.last_session {
+            if session.path == *path {
+                self.last_session = None;
+            }
+        }
+    }
+
+    pub fn get_file_strings(&self) -> Vec<String> {
+        return self.files.iter().map(|v| VMRecentVersion1::display_path(v)).collect();
+    }
+
+    //Strips the extended-length prefix that canonicalize() adds on Windows.
+    pub fn display_path(path: &PathBuf) -> String {
+        let string = path.display().to_string();
+        if string.starts_with(r"\\?") {
+            return string[4..].to_string();
+        } else {
+            return string;
+        }
+    }
+}
+
+pub struct VMRecentSerde;
+
+#[allow(unused_must_use)]
+impl VMRecentSerde {
+    fn get_path() -> Option<PathBuf> {
+        if let Some(mut path) = dirs::config_dir() {
+            path.push(DEFAULT_CONFIG_DIR_NAME);
+            path.push(DEFAULT_RECENT_FILE_NAME);
+            return Some(path);
+        } else {
+            return None;
+        }
+    }
+
+    pub fn load() -> VMRecentVersion1 {
+        if let Some(path) = VMRecentSerde::get_path() {
+            if let Ok(string) = fs::read_to_string(path) {
+                if let Ok(recent) = serde_json::from_str::<VMRecentVersion1>(&string) {
+                    return recent;
+                }
+            }
+        }
+        VMRecentVersion1::default()
+    }
+
+    pub fn save(recent: &VMRecentVersion1) -> Result<String, String> {
+        if let Some(path) = VMRecentSerde::get_path() {
+            if let Some(dir) = path.parent() {
+                if !dir.exists() {
+                    fs::create_dir_all(dir);
+                }
+            }
+            if let Ok(string) = serde_json::to_string_pretty(recent) {
+                if let Ok(_) = fs::write(path, string) {
+                    return Ok("Recent files saved".to_string());
+                } else {
+                    return Err("Could not save recent files.".to_string());
+                }
+            } else {
+                return Err("Could not serialize recent files.".to_string());
+            }
+        } else {
+            return Err("No user config dir found.".to_string());
+        }
+    }
+}
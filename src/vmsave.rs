@@ -12,19 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeSet, BTreeMap};
 use std::fs;
 use std::path::{PathBuf, Path};
 
 use druid::{Vec2, Data, WidgetPod};
 use druid::kurbo::TranslateScale;
-use vm_force_graph_rs::{ForceGraph, DefaultNodeIdx, NodeData, EdgeData};
+use vm_force_graph_rs::{ForceGraph, DefaultNodeIdx, NodeData, EdgeData, LayoutMode};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
 use serde::{Serialize, Deserialize};
 
 use crate::{constants::*, VMTab};
 
 use crate::vimmapper::NodeRenderMode;
-use crate::vmnode::VMNode;
+use crate::vmnode::{VMNode, VMNodeReference};
+use crate::vmedge::{VMEdge, EdgeDirection};
+use crate::vmstyle::VMNodeStyle;
 use crate::{vmconfig::VMConfigVersion4, vimmapper::VimMapper};
 
 
@@ -62,6 +65,11 @@ pub struct VMTabSave {
     offset_x: f64,
     offset_y: f64,
     zoom_index: Option<usize>,
+    //Saves made before edges carried data have no edge map and share a dummy edge index of 0
+    edges: Option<HashMap<u32, BareEdgeVersion4>>,
+    edge_idx_count: Option<u32>,
+    //Absent in saves made before tabs could use tree layouts
+    layout_mode: Option<LayoutMode>,
 }
 
 impl From<VMSaveVersion4> for VMSaveVersion5 {
@@ -80,6 +88,9 @@ impl From<VMSaveVersion4> for VMSaveVersion5 {
                     offset_x: save.offset_x, 
                     offset_y: save.offset_y,
                     zoom_index: Some(0),
+                    edges: None,
+                    edge_idx_count: None,
+                    layout_mode: None,
                 }
             ],
             active_tab: 0,
@@ -168,7 +179,7 @@ impl From<VMSaveNoVersion> for VMSaveVersion4 {
             graph.add_edge(
                 nodes.get(&v.from).unwrap().fg_index.unwrap(), 
                 nodes.get(&v.to).unwrap().fg_index.unwrap(), 
-                EdgeData { user_data: v.index });
+                EdgeData { user_data: v.index, ..Default::default() });
         }
         tracing::debug!("coercing VMSaveNoVerion to VMSaveVersion4");
         let mut new_nodes = save.nodes.clone();
@@ -206,7 +217,28 @@ impl VMSaveSerde {
     pub(crate) fn from_save(save: VMSaveVersion5, config: VMConfigVersion4) -> (Vec<VMTab>, usize) {
         let mut vms: Vec<VMTab> = vec![];
         for tab in save.tabs {
-            let graph = tab.graph;
+            let mut graph = tab.graph;
+            let mut edges: HashMap<u32, VMEdge> = HashMap::new();
+            let mut edge_idx_count = tab.edge_idx_count.unwrap_or(0);
+            if let Some(bare_edges) = tab.edges {
+                for (_k, v) in bare_edges {
+                    edges.insert(v.index, VMEdge::with_fields(v.label, v.direction.unwrap_or_default(), v.index));
+                }
+            } else {
+                //Give each edge of an older save its own index
+                for edge in graph.get_graph_mut().edge_weights_mut() {
+                    edge.user_data = edge_idx_count;
+                    edge_idx_count += 1;
+                }
+            }
+            for edge in graph.get_graph().edge_weights() {
+                if !edges.contains_key(&edge.user_data) {
+                    edges.insert(edge.user_data, VMEdge::with_fields(None, EdgeDirection::None, edge.user_data));
+                }
+                if edge.user_data >= edge_idx_count {
+                    edge_idx_count = edge.user_data + 1;
+                }
+            }
             let mut nodes: HashMap<u32, VMNode> = HashMap::with_capacity(50);
             for (_k ,v) in tab.nodes {
                 let mut fg_index: DefaultNodeIdx = DefaultNodeIdx::default();
@@ -215,19 +247,29 @@ impl VMSaveSerde {
                         fg_index = n.index();
                     }
                 });
-                nodes.insert(v.index, VMNode::with_fields(
+                let mut node = VMNode::with_fields(
                     v.label.clone(), 
                     v.index, 
                     Some(fg_index), 
                     v.mark,
                     v.is_active,
-                ));
+                );
+                node.style = v.style.unwrap_or_default();
+                node.tags = v.tags.unwrap_or_default();
+                node.properties = v.properties.unwrap_or_default();
+                node.set_note(v.note);
+                node.link = v.link;
+                node.reference = v.reference;
+                node.folded = v.folded.unwrap_or_default();
+                nodes.insert(v.index, node);
             }
             let vm = VimMapper {
                 graph,
                 animating: true,
                 nodes,
                 node_idx_count: tab.node_idx_count,
+                edges,
+                edge_idx_count,
                 translate: TranslateScale::new(
                     Vec2::new(
                         tab.translate.0, 
@@ -247,6 +289,9 @@ impl VMSaveSerde {
                 node_render_mode: NodeRenderMode::AllEnabled,
                 root_nodes: tab.root_nodes,
                 zoom_level_index: if let Some(index) = tab.zoom_index { index } else { DEFAULT_ZOOM_INDEX },
+                //Hide the descendants of folded nodes on the first layout
+                folds_stale: true,
+                layout_mode: tab.layout_mode.unwrap_or_default(),
                 ..Default::default()
             };
             vms.push(VMTab {vm: WidgetPod::new(vm), tab_name: tab.tab_name});
@@ -271,9 +316,27 @@ impl VMSaveSerde {
                     targeted_internal_edge_idx: None,
                     mark: node.mark.clone(),
                     mass: vm.graph.get_graph()[node.fg_index.unwrap()].data.mass,
-                    anchored: vm.graph.get_graph()[node.fg_index.unwrap()].data.is_anchor
+                    anchored: vm.graph.get_graph()[node.fg_index.unwrap()].data.is_anchor,
+                    style: if node.style.is_empty() { None } else { Some(node.style.clone()) },
+                    tags: if node.tags.is_empty() { None } else { Some(node.tags.clone()) },
+                    properties: if node.properties.is_empty() { None } else { Some(node.properties.clone()) },
+                    note: node.get_note(),
+                    link: node.link.clone(),
+                    reference: node.reference.clone(),
+                    folded: if node.folded { Some(true) } else { None },
                 });
             });
+            let mut edges: HashMap<u32, BareEdgeVersion4> = HashMap::new();
+            for edge in vm.graph.get_graph().edge_references() {
+                let index = edge.weight().user_data;
+                edges.insert(index, BareEdgeVersion4 {
+                    label: vm.edges.get(&index).and_then(|e| e.get_label()),
+                    direction: vm.edges.get(&index).map(|e| e.get_direction()),
+                    from: vm.graph.get_graph()[edge.source()].data.user_data,
+                    to: vm.graph.get_graph()[edge.target()].data.user_data,
+                    index,
+                });
+            }
             let save = VMTabSave {
                 tab_name: tab.tab_name.clone(),
                 graph: vm.graph.clone(),
@@ -285,6 +348,9 @@ impl VMSaveSerde {
                 offset_y: vm.get_offset_y(),
                 root_nodes: vm.root_nodes.clone(),
                 zoom_index: Some(vm.zoom_level_index),
+                edges: Some(edges),
+                edge_idx_count: Some(vm.edge_idx_count),
+                layout_mode: Some(vm.layout_mode),
             };
             tabs.push(save)
         }
@@ -347,6 +413,19 @@ pub struct BareNodeVersion4 {
     targeted_internal_edge_idx: Option<usize>,
     mass: f64,
     anchored: bool,
+    //Absent in saves made before nodes could be styled and for nodes with no style overrides
+    style: Option<VMNodeStyle>,
+    //Absent in saves made before nodes carried tags and properties
+    tags: Option<BTreeSet<String>>,
+    properties: Option<BTreeMap<String, String>>,
+    //Absent in saves made before nodes could carry notes
+    note: Option<String>,
+    //Absent in saves made before nodes could link to URLs and files
+    link: Option<String>,
+    //Absent in saves made before nodes could refer to nodes in other tabs
+    reference: Option<VMNodeReference>,
+    //Absent in saves made before subtrees could be folded and for unfolded nodes
+    folded: Option<bool>,
 }
 
 impl Default for BareNodeVersion4 {
@@ -360,7 +439,14 @@ impl Default for BareNodeVersion4 {
             mark: None, 
             targeted_internal_edge_idx: None, 
             mass: DEFAULT_NODE_MASS, 
-            anchored: false 
+            anchored: false,
+            style: None,
+            tags: None,
+            properties: None,
+            note: None,
+            link: None,
+            reference: None,
+            folded: None,
         }
     }
 }
@@ -370,6 +456,8 @@ impl Default for BareNodeVersion4 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct BareEdgeVersion4 {
     label: Option<String>,
+    //Relative to from -> to. Absent in saves made before edges had a direction.
+    direction: Option<EdgeDirection>,
     from: u32,
     to: u32,
     index: u32,
@@ -379,6 +467,7 @@ impl Default for BareEdgeVersion4 {
     fn default() -> Self {
         BareEdgeVersion4 {
             label: None,
+            direction: None,
             from: 0,
             to: 0,
             index: 0,
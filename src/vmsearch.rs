@@ -0,0 +1,256 @@
+// Copyright 2022 Doug Powers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+pub const REGEX_SEARCH_PREFIX: char = '/';
+pub const LITERAL_SEARCH_PREFIX: char = '\'';
+
+//Scores given to matched characters, loosely following fzf. Matches that start words or run together
+// rank above matches scattered through the text.
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CONSECUTIVE: i64 = 4;
+
+//Returns whether the search string is fuzzy rather than starting with a regex or literal prefix.
+pub fn is_fuzzy_search(query: &str) -> bool {
+    return !query.starts_with(REGEX_SEARCH_PREFIX) && !query.starts_with(LITERAL_SEARCH_PREFIX);
+}
+
+//Matches the label part of a search string. Searches are fuzzy unless the string starts with / for a
+// regex or ' for a literal. All three modes ignore case.
+pub enum SearchMatcher {
+    //Each whitespace-separated term must match on its own, with its characters in order but not
+    // necessarily next to each other
+    Fuzzy(Vec<Vec<char>>),
+    Regex(Regex),
+    Literal(Vec<char>),
+}
+
+pub struct SearchMatch {
+    pub score: i64,
+    //Byte ranges of the matched characters
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl SearchMatcher {
+    //Builds a matcher for a search string. Returns a short description of the problem if the string is an
+    // invalid regex.
+    pub fn new(query: &str) -> Result<SearchMatcher, String> {
+        if let Some(pattern) = query.strip_prefix(REGEX_SEARCH_PREFIX) {
+            return RegexBuilder::new(pattern).case_insensitive(true).build()
+                .map(SearchMatcher::Regex)
+                .map_err(|err| {
+                    //Syntax errors span several lines to point at the problem. The last line describes it.
+                    let message = err.to_string();
+                    return message.lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+                });
+        } else if let Some(text) = query.strip_prefix(LITERAL_SEARCH_PREFIX) {
+            return Ok(SearchMatcher::Literal(text.chars().collect()));
+        } else {
+            return Ok(SearchMatcher::Fuzzy(query.split_whitespace().map(|term| term.chars().collect()).collect()));
+        }
+    }
+
+    //Matches the text, returning its score and the characters that matched. An empty search matches
+    // everything with a score of 0.
+    pub fn find(&self, text: &str) -> Option<SearchMatch> {
+        let chars = text.char_indices().collect::<Vec<(usize, char)>>();
+        let positions = match self {
+            SearchMatcher::Fuzzy(terms) => {
+                let mut positions = vec![];
+                for term in terms {
+                    positions.extend(fuzzy_positions(&chars, term)?);
+                }
+                positions.sort_unstable();
+                positions.dedup();
+                positions
+            }
+            SearchMatcher::Regex(regex) => {
+                let found = regex.find(text)?;
+                (0..chars.len()).filter(|pos| found.range().contains(&chars[*pos].0)).collect()
+            }
+            SearchMatcher::Literal(literal) => {
+                literal_positions(&chars, literal)?
+            }
+        };
+        return Some(SearchMatch {
+            score: score_positions(&chars, &positions),
+            ranges: byte_ranges(&chars, &positions),
+        });
+    }
+}
+
+fn chars_match(a: char, b: char) -> bool {
+    return a == b || a.to_lowercase().eq(b.to_lowercase());
+}
+
+//Finds the term as a subsequence of the text. The first complete match is found going forward, then the
+// match is tightened by walking back from its end so that the matched characters sit as close together as
+// possible.
+fn fuzzy_positions(chars: &[(usize, char)], term: &[char]) -> Option<Vec<usize>> {
+    if term.is_empty() {
+        return Some(vec![]);
+    }
+    let mut matched = 0;
+    let mut end = None;
+    for (pos, (_, c)) in chars.iter().enumerate() {
+        if chars_match(*c, term[matched]) {
+            matched += 1;
+            if matched == term.len() {
+                end = Some(pos);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut positions = vec![];
+    let mut remaining = term.len();
+    for pos in (0..=end).rev() {
+        if chars_match(chars[pos].1, term[remaining - 1]) {
+            positions.push(pos);
+            remaining -= 1;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+    return Some(positions);
+}
+
+fn literal_positions(chars: &[(usize, char)], literal: &[char]) -> Option<Vec<usize>> {
+    if literal.is_empty() {
+        return Some(vec![]);
+    }
+    if literal.len() > chars.len() {
+        return None;
+    }
+    let start = (0..=chars.len() - literal.len()).find(|start| {
+        return literal.iter().enumerate().all(|(offset, c)| chars_match(chars[start + offset].1, *c));
+    })?;
+    return Some((start..start + literal.len()).collect());
+}
+
+fn score_positions(chars: &[(usize, char)], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    for (n, pos) in positions.iter().enumerate() {
+        score += SCORE_MATCH;
+        let c = chars[*pos].1;
+        let is_boundary = *pos == 0 || {
+            let previous = chars[*pos - 1].1;
+            !previous.is_alphanumeric() || (previous.is_lowercase() && c.is_uppercase())
+        };
+        if is_boundary {
+            score += BONUS_BOUNDARY;
+        }
+        if n > 0 {
+            let gap = (*pos - positions[n - 1] - 1) as i64;
+            if gap == 0 {
+                score += BONUS_CONSECUTIVE;
+            } else {
+                score += SCORE_GAP_START + SCORE_GAP_EXTENSION * (gap - 1);
+            }
+        }
+    }
+    return score;
+}
+
+//Converts character positions to byte ranges, joining runs of adjacent characters.
+fn byte_ranges(chars: &[(usize, char)], positions: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for pos in positions {
+        let (start, c) = chars[*pos];
+        let end = start + c.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+    return ranges;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_ranking() {
+        let matcher = SearchMatcher::new("lgn").unwrap();
+        let word_starts = matcher.find("Login page").unwrap();
+        let scattered = matcher.find("blog entry").unwrap();
+        assert!(word_starts.score > scattered.score);
+        assert_eq!(word_starts.ranges, vec![0..1, 2..3, 4..5]);
+        assert!(matcher.find("no match here").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_terms() {
+        let matcher = SearchMatcher::new("pg lo").unwrap();
+        assert!(matcher.find("Login page").is_some());
+        assert!(matcher.find("page").is_none());
+    }
+
+    #[test]
+    fn test_regex() {
+        let matcher = SearchMatcher::new("/^to.o\\b").unwrap();
+        assert_eq!(matcher.find("Todo: ship it").unwrap().ranges, vec![0..4]);
+        assert!(matcher.find("a todo").is_none());
+    }
+
+    #[test]
+    fn test_regex_error() {
+        assert_eq!(SearchMatcher::new("/(").err(), Some(String::from("unclosed group")));
+        assert_eq!(SearchMatcher::new("/[").err(), Some(String::from("unclosed character class")));
+    }
+
+    #[test]
+    fn test_literal() {
+        let matcher = SearchMatcher::new("'n p").unwrap();
+        assert_eq!(matcher.find("Login page").unwrap().ranges, vec![4..7]);
+        assert!(matcher.find("Login  page").is_none());
+        assert!(SearchMatcher::new("'lgn").unwrap().find("Login").is_none());
+    }
+
+    #[test]
+    fn test_case_folding() {
+        assert!(SearchMatcher::new("LOGIN").unwrap().find("login").is_some());
+        assert!(SearchMatcher::new("/LOG").unwrap().find("login").is_some());
+        assert!(SearchMatcher::new("'ÉTÉ").unwrap().find("un été chaud").is_some());
+    }
+
+    #[test]
+    fn test_multi_byte_ranges() {
+        let matcher = SearchMatcher::new("ét").unwrap();
+        assert_eq!(matcher.find("un été").unwrap().ranges, vec![3..6]);
+    }
+
+    #[test]
+    fn test_empty_search() {
+        let found = SearchMatcher::new("").unwrap().find("anything").unwrap();
+        assert_eq!(found.score, 0);
+        assert!(found.ranges.is_empty());
+    }
+
+    #[test]
+    fn test_is_fuzzy_search() {
+        assert!(is_fuzzy_search("#bug login"));
+        assert!(!is_fuzzy_search("/#bug"));
+        assert!(!is_fuzzy_search("'owner:alice"));
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright 2022 Doug Powers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use druid::{Point, Rect};
+use vm_force_graph_rs::DefaultNodeIdx;
+
+//A uniform grid of node rects in canvas space. Each rect is filed under every cell it overlaps, so
+// finding the nodes at a point or inside a rect only looks at the nodes in the cells it covers instead of
+// every node in the sheet.
+pub struct VMSpatialIndex {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<DefaultNodeIdx>>,
+    rects: HashMap<DefaultNodeIdx, Rect>,
+}
+
+impl VMSpatialIndex {
+    pub fn new(cell_size: f64) -> Self {
+        return VMSpatialIndex {
+            cell_size,
+            cells: HashMap::new(),
+            rects: HashMap::new(),
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.rects.clear();
+    }
+
+    pub fn insert(&mut self, idx: DefaultNodeIdx, rect: Rect) {
+        let (x0, y0, x1, y1) = self.cell_range(rect);
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.cells.entry((x, y)).or_default().push(idx);
+            }
+        }
+        self.rects.insert(idx, rect);
+    }
+
+    pub fn get_rect(&self, idx: DefaultNodeIdx) -> Option<Rect> {
+        return self.rects.get(&idx).cloned();
+    }
+
+    //Returns the nodes whose rects contain the point, in index order.
+    pub fn query_point(&self, point: Point) -> Vec<DefaultNodeIdx> {
+        let mut hits = vec![];
+        if let Some(cell) = self.cells.get(&self.cell_of(point)) {
+            for idx in cell {
+                if self.rects[idx].contains(point) {
+                    hits.push(*idx);
+                }
+            }
+        }
+        hits.sort();
+        return hits;
+    }
+
+    //Returns the nodes whose rects overlap the given rect.
+    pub fn query_rect(&self, rect: Rect) -> HashSet<DefaultNodeIdx> {
+        let (x0, y0, x1, y1) = self.cell_range(rect);
+        let cell_count = (x1 - x0 + 1).saturating_mul(y1 - y0 + 1);
+        //When zoomed far out the rect can cover more cells than are occupied, so the nodes are checked
+        // directly instead
+        if cell_count as usize > self.cells.len() {
+            return self.rects.iter()
+                .filter(|(_, node_rect)| overlaps(rect, **node_rect))
+                .map(|(idx, _)| *idx)
+                .collect();
+        }
+        let mut hits = HashSet::new();
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    for idx in cell {
+                        if overlaps(rect, self.rects[idx]) {
+                            hits.insert(*idx);
+                        }
+                    }
+                }
+            }
+        }
+        return hits;
+    }
+
+    fn cell_of(&self, point: Point) -> (i64, i64) {
+        return ((point.x / self.cell_size).floor() as i64, (point.y / self.cell_size).floor() as i64);
+    }
+
+    fn cell_range(&self, rect: Rect) -> (i64, i64, i64, i64) {
+        let rect = rect.abs();
+        let (x0, y0) = self.cell_of(Point::new(rect.x0, rect.y0));
+        let (x1, y1) = self.cell_of(Point::new(rect.x1, rect.y1));
+        return (x0, y0, x1, y1);
+    }
+}
+
+//Returns whether the line between two points could cross the rect. Lines whose bounding boxes miss the
+// rect entirely are never visible in it.
+pub fn line_may_cross(rect: Rect, p0: Point, p1: Point) -> bool {
+    return overlaps(rect, Rect::from_points(p0, p1));
+}
+
+//Unlike Rect::intersect, rects that only touch or have no area (like the bounds of a vertical line) still
+// count as overlapping.
+fn overlaps(a: Rect, b: Rect) -> bool {
+    return a.x0 <= b.x1 && b.x0 <= a.x1 && a.y0 <= b.y1 && b.y0 <= a.y1;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn node(n: usize) -> DefaultNodeIdx {
+        return DefaultNodeIdx::new(n);
+    }
+
+    #[test]
+    fn test_empty_index() {
+        let index = VMSpatialIndex::new(100.);
+        assert!(index.query_point(Point::new(0., 0.)).is_empty());
+        assert!(index.query_rect(Rect::new(-1000., -1000., 1000., 1000.)).is_empty());
+        assert_eq!(index.get_rect(node(0)), None);
+    }
+
+    #[test]
+    fn test_rect_spanning_cells() {
+        let mut index = VMSpatialIndex::new(100.);
+        index.insert(node(1), Rect::new(50., 50., 250., 150.));
+        index.insert(node(2), Rect::new(260., 10., 290., 40.));
+        assert_eq!(index.query_point(Point::new(60., 60.)), vec![node(1)]);
+        assert_eq!(index.query_point(Point::new(220., 120.)), vec![node(1)]);
+        assert!(index.query_point(Point::new(270., 120.)).is_empty());
+        let hits = index.query_rect(Rect::new(200., 100., 210., 110.));
+        assert_eq!(hits, HashSet::from([node(1)]));
+        let hits = index.query_rect(Rect::new(240., 0., 300., 50.));
+        assert_eq!(hits, HashSet::from([node(1), node(2)]));
+    }
+
+    #[test]
+    fn test_negative_coordinates() {
+        let mut index = VMSpatialIndex::new(100.);
+        index.insert(node(1), Rect::new(-150., -150., -50., -50.));
+        index.insert(node(2), Rect::new(-10., -10., 10., 10.));
+        assert_eq!(index.query_point(Point::new(-100., -100.)), vec![node(1)]);
+        assert_eq!(index.query_point(Point::new(-5., -5.)), vec![node(2)]);
+        assert_eq!(index.query_point(Point::new(5., 5.)), vec![node(2)]);
+        assert!(index.query_point(Point::new(-100., 100.)).is_empty());
+        let hits = index.query_rect(Rect::new(-60., -60., -20., -20.));
+        assert_eq!(hits, HashSet::from([node(1)]));
+    }
+
+    #[test]
+    fn test_query_rect_covering_more_cells_than_occupied() {
+        let mut index = VMSpatialIndex::new(100.);
+        index.insert(node(1), Rect::new(0., 0., 10., 10.));
+        index.insert(node(2), Rect::new(5000., 5000., 5010., 5010.));
+        let hits = index.query_rect(Rect::new(-10000., -10000., 10000., 10000.));
+        assert_eq!(hits, HashSet::from([node(1), node(2)]));
+        let hits = index.query_rect(Rect::new(-10000., -10000., 100., 100.));
+        assert_eq!(hits, HashSet::from([node(1)]));
+    }
+
+    #[test]
+    fn test_line_may_cross() {
+        let rect = Rect::new(0., 0., 10., 10.);
+        //Crosses the rect with both endpoints outside it
+        assert!(line_may_cross(rect, Point::new(-20., 5.), Point::new(20., 5.)));
+        assert!(line_may_cross(rect, Point::new(5., 20.), Point::new(5., -20.)));
+        assert!(!line_may_cross(rect, Point::new(-20., 20.), Point::new(20., 30.)));
+        assert!(!line_may_cross(rect, Point::new(11., -20.), Point::new(11., 20.)));
+    }
+}
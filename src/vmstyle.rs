@@ -0,0 +1,178 @@
+// Copyright 2022 Doug Powers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use druid::{Color, FontWeight, Rect};
+use serde::{Serialize, Deserialize};
+
+use crate::constants::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BorderShape {
+    Rounded,
+    Square,
+    Pill,
+}
+
+//Per-node overrides of the global palette and label font. Any field left as None falls back to the
+// value from the config (or the default font). Colors use the same (r,g,b,a) format as the palettes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VMNodeStyle {
+    pub fill_color: Option<(u8,u8,u8,u8)>,
+    pub border_color: Option<(u8,u8,u8,u8)>,
+    pub text_color: Option<(u8,u8,u8,u8)>,
+    pub border_shape: Option<BorderShape>,
+    pub font_weight: Option<u16>,
+    pub font_size: Option<f64>,
+}
+
+impl VMNodeStyle {
+    pub fn is_empty(&self) -> bool {
+        return *self == VMNodeStyle::default();
+    }
+
+    //Layers another style over this one. Fields set in the other style take precedence so that, for
+    // example, a color style and a shape style can both be applied to the same node.
+    pub fn merge(&mut self, other: &VMNodeStyle) {
+        if other.fill_color.is_some() { self.fill_color = other.fill_color; }
+        if other.border_color.is_some() { self.border_color = other.border_color; }
+        if other.text_color.is_some() { self.text_color = other.text_color; }
+        if other.border_shape.is_some() { self.border_shape = other.border_shape; }
+        if other.font_weight.is_some() { self.font_weight = other.font_weight; }
+        if other.font_size.is_some() { self.font_size = other.font_size; }
+    }
+
+    pub fn get_fill_color(&self) -> Option<Color> {
+        self.fill_color.map(|(r,g,b,a)| Color::rgba8(r,g,b,a))
+    }
+
+    pub fn get_border_color(&self) -> Option<Color> {
+        self.border_color.map(|(r,g,b,a)| Color::rgba8(r,g,b,a))
+    }
+
+    pub fn get_text_color(&self) -> Option<Color> {
+        self.text_color.map(|(r,g,b,a)| Color::rgba8(r,g,b,a))
+    }
+
+    pub fn get_font_weight(&self) -> FontWeight {
+        if let Some(weight) = self.font_weight {
+            return FontWeight::new(weight);
+        } else {
+            return FontWeight::REGULAR;
+        }
+    }
+
+    pub fn get_font_size(&self) -> f64 {
+        if let Some(size) = self.font_size {
+            return size;
+        } else {
+            return DEFAULT_LABEL_FONT_SIZE;
+        }
+    }
+
+    //Corner radius for the node border drawn around the given rect.
+    pub fn get_border_radius(&self, rect: &Rect) -> f64 {
+        match self.border_shape {
+            Some(BorderShape::Square) => 0.,
+            Some(BorderShape::Pill) => rect.height().min(rect.width()) / 2.,
+            _ => DEFAULT_BORDER_RADIUS,
+        }
+    }
+}
+
+//A style that can be applied from the style picker by pressing its key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VMNamedStyle {
+    pub name: String,
+    pub key: String,
+    pub style: VMNodeStyle,
+}
+
+impl VMNamedStyle {
+    pub fn default_styles() -> Vec<VMNamedStyle> {
+        vec![
+            VMNamedStyle {
+                name: String::from("red/urgent"),
+                key: String::from("r"),
+                style: VMNodeStyle {
+                    fill_color: Some((200,50,50,255)),
+                    border_color: Some((120,20,20,255)),
+                    text_color: Some((255,255,255,255)),
+                    ..Default::default()
+                },
+            },
+            VMNamedStyle {
+                name: String::from("yellow/waiting"),
+                key: String::from("y"),
+                style: VMNodeStyle {
+                    fill_color: Some((240,200,60,255)),
+                    border_color: Some((150,120,20,255)),
+                    text_color: Some((0,0,0,255)),
+                    ..Default::default()
+                },
+            },
+            VMNamedStyle {
+                name: String::from("green/done"),
+                key: String::from("g"),
+                style: VMNodeStyle {
+                    fill_color: Some((70,160,80,255)),
+                    border_color: Some((30,90,40,255)),
+                    text_color: Some((255,255,255,255)),
+                    ..Default::default()
+                },
+            },
+            VMNamedStyle {
+                name: String::from("blue/info"),
+                key: String::from("b"),
+                style: VMNodeStyle {
+                    fill_color: Some((70,110,200,255)),
+                    border_color: Some((30,50,120,255)),
+                    text_color: Some((255,255,255,255)),
+                    ..Default::default()
+                },
+            },
+            VMNamedStyle {
+                name: String::from("square"),
+                key: String::from("s"),
+                style: VMNodeStyle {
+                    border_shape: Some(BorderShape::Square),
+                    ..Default::default()
+                },
+            },
+            VMNamedStyle {
+                name: String::from("pill"),
+                key: String::from("p"),
+                style: VMNodeStyle {
+                    border_shape: Some(BorderShape::Pill),
+                    ..Default::default()
+                },
+            },
+            VMNamedStyle {
+                name: String::from("bold"),
+                key: String::from("B"),
+                style: VMNodeStyle {
+                    font_weight: Some(700),
+                    ..Default::default()
+                },
+            },
+            VMNamedStyle {
+                name: String::from("large"),
+                key: String::from("L"),
+                style: VMNodeStyle {
+                    font_size: Some(28.),
+                    ..Default::default()
+                },
+            },
+        ]
+    }
+}
@@ -16,7 +16,7 @@ use std::ops::Range;
 
 use druid::{EventCtx, LayoutCtx, piet::{PietTextLayout, TextLayout, Text, TextLayoutBuilder}, PaintCtx, RenderContext, Point, Rect, BoxConstraints, Size, text::{EditableText}, Color, FontFamily, Vec2, Affine};
 
-use crate::{vminput::{ActionPayload, Action, KeybindMode, TextOperation, TextObj, TextMotion}, vmconfig::{VMConfigVersion4, VMColor}, constants::{NODE_LABEL_MAX_CONSTRAINTS, DEFUALT_TEXT_CURSOR_WIDTH, TEXT_HISTORY_SIZE}, vimmapper::VimMapper};
+use crate::{vminput::{ActionPayload, Action, KeybindMode, TextOperation, TextObj, TextMotion}, vmconfig::{VMConfigVersion4, VMColor}, constants::{NODE_LABEL_MAX_CONSTRAINTS, DEFUALT_TEXT_CURSOR_WIDTH, TEXT_HISTORY_SIZE, DEFAULT_NOTE_FONT_SIZE}, vimmapper::VimMapper, vmstyle::VMNodeStyle};
 
 use unicode_segmentation::*;
 
@@ -370,16 +370,30 @@ impl<'a> VMTextInput {
                                         }
                                     },
                                     TextMotion::BeginningLine => {
-                                        self.text.edit(0..self.index, "");
-                                        self.cursor_to_start();
+                                        let line_start = self.line_start(self.index);
+                                        self.text.edit(line_start..self.index, "");
+                                        self.index = line_start;
                                     },
                                     TextMotion::EndLine => {
-                                        self.text.edit(self.index..self.text.len(), "");
+                                        let line_end = self.line_end(self.index);
+                                        self.text.edit(self.index..line_end, "");
                                     },
                                     TextMotion::WholeLine => {
-                                        self.text.edit(0..self.text.len(), "");
-                                        self.set_cursor(Some(0));
-                                    }
+                                        let mut line_start = self.line_start(self.index);
+                                        let mut line_end = self.line_end(self.index);
+                                        //Deleting a line also removes its line break. Changing it leaves an empty
+                                        // line to type into.
+                                        if text_action.operation == TextOperation::DeleteText {
+                                            if line_end < self.text.len() {
+                                                line_end += 1;
+                                            } else if line_start > 0 {
+                                                line_start -= 1;
+                                            }
+                                        }
+                                        self.text.edit(line_start..line_end, "");
+                                        self.index = self.line_start(line_start.min(self.text.len()));
+                                    },
+                                    TextMotion::ForwardLine | TextMotion::BackwardLine => (),
                                 }
                             }
                         },
@@ -429,14 +443,33 @@ impl<'a> VMTextInput {
                                         }
                                     },
                                     TextMotion::BeginningLine => {
-                                        self.set_cursor(Some(0));
+                                        self.set_cursor(Some(self.line_start(self.index)));
                                     },
                                     TextMotion::EndLine => {
-                                        self.set_cursor(Some(self.text.len()));
+                                        let line_end = self.line_end(self.index);
+                                        if self.mode != KeybindMode::Insert && line_end < self.text.len() && line_end > self.line_start(self.index) {
+                                            self.set_cursor(self.text.prev_grapheme_offset(line_end));
+                                        } else {
+                                            self.set_cursor(Some(line_end));
+                                        }
                                     },
                                     TextMotion::WholeLine => {
 
-                                    }
+                                    },
+                                    TextMotion::ForwardLine => {
+                                        let line_end = self.line_end(self.index);
+                                        if line_end < self.text.len() {
+                                            let column = self.text[self.line_start(self.index)..self.index].chars().count();
+                                            self.cursor_to_column(line_end + 1, column);
+                                        }
+                                    },
+                                    TextMotion::BackwardLine => {
+                                        let line_start = self.line_start(self.index);
+                                        if line_start > 0 {
+                                            let column = self.text[line_start..self.index].chars().count();
+                                            self.cursor_to_column(self.line_start(line_start - 1), column);
+                                        }
+                                    },
                                 }
                             }
                         }
@@ -544,6 +577,9 @@ impl<'a> VMTextInput {
                         6.,
                         metric.height,
                     );
+            } else if self.text[index..].starts_with('\n') || (index == self.text.len() && self.text.ends_with('\n')) {
+                let rect = self.get_empty_position_bounds(layout, index);
+                return Rect::new(rect.x0, rect.y0, rect.x0+6., rect.y1);
             } else if let Some(next_index) = self.text.next_grapheme_offset(index) {
                 let rects = layout.rects_for_range(index..next_index);
                 return rects[0];
@@ -571,6 +607,9 @@ impl<'a> VMTextInput {
                         DEFUALT_TEXT_CURSOR_WIDTH,
                         metric.height,
                     );
+            } else if self.text[index..].starts_with('\n') || (index == self.text.len() && self.text.ends_with('\n')) {
+                let rect = self.get_empty_position_bounds(layout, index);
+                return Rect::new(rect.x0, rect.y0, rect.x0+DEFUALT_TEXT_CURSOR_WIDTH, rect.y1);
             } else if let Some(next_index) = self.text.next_grapheme_offset(index) {
                 let rects = layout.rects_for_range(index..next_index);
                 return Rect::new(
@@ -593,6 +632,45 @@ impl<'a> VMTextInput {
         }
     }
 
+    //Byte offset of the start of the line containing index.
+    fn line_start(&self, index: usize) -> usize {
+        return self.text[..index].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    }
+
+    //Byte offset of the end of the line containing index. This is the offset of the line's newline or the
+    // end of the text for the last line.
+    fn line_end(&self, index: usize) -> usize {
+        return self.text[index..].find('\n').map(|i| i + index).unwrap_or(self.text.len());
+    }
+
+    //Moves the cursor to the given character column of the line starting at line_start, or to the end of
+    // that line if it's shorter.
+    fn cursor_to_column(&mut self, line_start: usize, column: usize) {
+        let line_end = self.line_end(line_start);
+        let target = self.text[line_start..line_end].char_indices()
+            .nth(column)
+            .map(|(i, _)| i + line_start)
+            .unwrap_or(line_end);
+        if self.mode != KeybindMode::Insert && target == line_end && line_end > line_start {
+            self.index = self.text.prev_grapheme_offset(line_end).unwrap_or(line_start);
+        } else {
+            self.index = target;
+        }
+    }
+
+    //A zero-width rect spanning the line at index. Used for cursor positions that have no glyph to measure,
+    // such as a newline or the start of an empty last line.
+    fn get_empty_position_bounds(&self, layout: &PietTextLayout, index: usize) -> Rect {
+        let position = layout.hit_test_text_position(index);
+        let metric = layout.line_metric(position.line).unwrap();
+        return Rect::new(
+            position.point.x,
+            metric.y_offset,
+            position.point.x,
+            metric.y_offset + metric.height,
+        );
+    }
+
     pub fn cursor_to_end(&mut self) {
         self.index = self.text.len();
     }
@@ -648,14 +726,29 @@ impl<'a> VMTextInput {
         return Err(());
     }
 
-    pub fn layout(&mut self, ctx: &mut LayoutCtx, config: &VMConfigVersion4) {
+    pub fn layout(&mut self, ctx: &mut LayoutCtx, config: &VMConfigVersion4, style: &VMNodeStyle) {
         let layout = VimMapper::build_label_layout_for_constraints(
             ctx.text(), 
             self.text.clone(),
+            &[],
             BoxConstraints::new(
                         Size::new(0., 0.),
                         Size::new(NODE_LABEL_MAX_CONSTRAINTS.0, NODE_LABEL_MAX_CONSTRAINTS.1)),
-            &config.get_color(VMColor::LabelTextColor).unwrap())
+            &style.get_text_color().unwrap_or(config.get_color(VMColor::LabelTextColor).unwrap()),
+            style)
+            .unwrap();
+
+        self.text_layout = Some(layout);
+    }
+
+    //Lays out the text for the notes panel. Unlike node labels, notes wrap to the given width and grow
+    // downward rather than shrinking to fit.
+    pub fn layout_note(&mut self, ctx: &mut LayoutCtx, config: &VMConfigVersion4, width: f64) {
+        let layout = ctx.text().new_text_layout(self.text.clone())
+            .font(FontFamily::SANS_SERIF, DEFAULT_NOTE_FONT_SIZE)
+            .text_color(config.get_color(VMColor::LabelTextColor).unwrap())
+            .max_width(width)
+            .build()
             .unwrap();
 
         self.text_layout = Some(layout);
@@ -0,0 +1,73 @@
+//! Compares the cost of a simulation step with exact and Barnes–Hut repulsion.
+//!
+//! Run with `cargo bench --bench repulsion`. Each graph is a tree with four children per node, scattered
+//! over a square so that every node has a similar number of close neighbors.
+
+use std::time::{Duration, Instant};
+
+use vm_force_graph_rs::{ForceGraph, NodeData, SimulationParameters};
+
+const NODE_COUNTS: [usize; 3] = [1_000, 5_000, 20_000];
+const THETA: f64 = 0.5;
+//Steps are repeated until this much time has passed to smooth out the faster timings
+const MIN_SAMPLE_TIME: Duration = Duration::from_millis(500);
+
+fn build_graph(count: usize, theta: Option<f64>) -> ForceGraph {
+    let mut graph = <ForceGraph>::new(SimulationParameters {
+        force_charge: 1000.,
+        force_spring: 4.0,
+        node_speed: 3000.,
+        damping_factor: 0.5,
+        min_attract_distance: 180.,
+        barnes_hut_theta: theta,
+        ..Default::default()
+    });
+    let spread = (count as f64).sqrt() * 200.;
+    let mut seed: u64 = 12345;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64 * spread
+    };
+    let mut indices = vec![];
+    for i in 0..count {
+        let (x, y) = (next(), next());
+        let idx = graph.add_node(NodeData {
+            x,
+            y,
+            repel_distance: 150.,
+            is_anchor: i == 0,
+            ..Default::default()
+        });
+        if i > 0 {
+            graph.add_edge(indices[(i - 1) / 4], idx, Default::default());
+        }
+        indices.push(idx);
+    }
+    graph
+}
+
+//Returns the average duration of one update
+fn time_update(graph: &mut ForceGraph) -> Duration {
+    let start = Instant::now();
+    let mut steps = 0;
+    while steps == 0 || start.elapsed() < MIN_SAMPLE_TIME {
+        graph.update(0.032);
+        steps += 1;
+    }
+    start.elapsed() / steps
+}
+
+fn main() {
+    println!("{:>8} {:>14} {:>14} {:>9}", "nodes", "exact", "barnes-hut", "speedup");
+    for count in NODE_COUNTS {
+        let exact = time_update(&mut build_graph(count, None));
+        let barnes_hut = time_update(&mut build_graph(count, Some(THETA)));
+        println!(
+            "{:>8} {:>14?} {:>14?} {:>8.1}x",
+            count,
+            exact,
+            barnes_hut,
+            exact.as_secs_f64() / barnes_hut.as_secs_f64(),
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Separation of overlapping node boxes.
+//!
+//! Repulsion treats nodes as points, so nodes with wide labels can still overlap once the simulation
+//! settles. After each step, boxes that overlap are pushed apart until they only touch.
+
+/// A node's box as seen by the collision solver.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NodeBox {
+    /// Position of the node in the list that corrections are returned for.
+    pub slot: usize,
+    pub x: f64,
+    pub y: f64,
+    pub half_width: f64,
+    pub half_height: f64,
+    pub is_anchor: bool,
+}
+
+impl NodeBox {
+    fn left(&self) -> f64 {
+        self.x - self.half_width
+    }
+
+    fn right(&self) -> f64 {
+        self.x + self.half_width
+    }
+}
+
+/// Returns the correction that separates each box from the boxes it overlaps, indexed by slot.
+///
+/// Each overlapping pair is pushed apart along the axis where it overlaps least. The correction is split
+/// evenly between the two boxes unless one is anchored, in which case the other takes all of it. Pairs
+/// are visited from left to right and each correction is applied before the next pair is checked, so the
+/// result depends only on where the boxes are. A box caught between several others may need a few passes
+/// to come free.
+pub(crate) fn separate(boxes: &[NodeBox], slots: usize) -> Vec<(f64, f64)> {
+    let mut sorted = boxes.to_vec();
+    sorted.sort_by(|a, b| a.left().partial_cmp(&b.left()).unwrap_or(std::cmp::Ordering::Equal).then(a.slot.cmp(&b.slot)));
+    let original = sorted.clone();
+    for i in 0..sorted.len() {
+        for j in i + 1..sorted.len() {
+            let (b1, b2) = (sorted[i], sorted[j]);
+            //Boxes are swept from left to right, so only those starting before this one ends can overlap it
+            if b2.left() >= b1.right() {
+                break;
+            }
+            if b1.is_anchor && b2.is_anchor {
+                continue;
+            }
+            let overlap_x = b1.half_width + b2.half_width - (b2.x - b1.x).abs();
+            let overlap_y = b1.half_height + b2.half_height - (b2.y - b1.y).abs();
+            if overlap_x <= 0. || overlap_y <= 0. {
+                continue;
+            }
+            //The push moves b1 away from b2. Boxes sharing a center are split by slot.
+            let away = |d: f64| {
+                if d < 0. || (d == 0. && b1.slot < b2.slot) { -1. } else { 1. }
+            };
+            let push = if overlap_x < overlap_y {
+                (away(b1.x - b2.x) * overlap_x, 0.)
+            } else {
+                (0., away(b1.y - b2.y) * overlap_y)
+            };
+            let share = if b1.is_anchor {
+                0.
+            } else if b2.is_anchor {
+                1.
+            } else {
+                0.5
+            };
+            sorted[i].x += push.0 * share;
+            sorted[i].y += push.1 * share;
+            sorted[j].x -= push.0 * (1. - share);
+            sorted[j].y -= push.1 * (1. - share);
+        }
+    }
+    let mut corrections = vec![(0., 0.); slots];
+    for (moved, original) in sorted.iter().zip(original.iter()) {
+        corrections[moved.slot] = (moved.x - original.x, moved.y - original.y);
+    }
+    corrections
+}
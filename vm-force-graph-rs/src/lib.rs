@@ -66,22 +66,31 @@
 //!
 //! ```
 
-use std::collections::{HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
 
 use petgraph::{
     stable_graph::{NodeIndex, StableUnGraph},
-    visit::{EdgeRef, IntoEdgeReferences},
-    algo::{all_simple_paths, TarjanScc},
+    visit::{EdgeRef, IntoEdgeReferences, VisitMap},
+    algo::TarjanScc,
 };
-// use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
 
-// use rayon::prelude::*;
-
-// use std::collections::BTreeSet;
+mod collision;
+mod quadtree;
+use collision::{separate, NodeBox};
+use quadtree::{Body, QuadTree};
 
 pub type DefaultNodeIdx = NodeIndex<petgraph::stable_graph::DefaultIx>;
 
+/// The step used by [ForceGraph::run_until_settled].
+pub const FIXED_STEP_DELTA: f64 = 0.032;
+
+//Most passes made over the node boxes after each step to push apart those that overlap
+const COLLISION_PASSES: usize = 16;
+
 /// Parameters to control the simulation of the force graph.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SimulationParameters {
@@ -91,6 +100,42 @@ pub struct SimulationParameters {
     pub node_speed: f64,
     pub damping_factor: f64,
     pub min_attract_distance: f64,
+    /// Rest length of edges that don't set their own.
+    ///
+    /// An edge pulls its nodes together with a force proportional to how far it's stretched beyond its rest
+    /// length. At 0.0 the edge always pulls and repulsion alone keeps the nodes apart.
+    #[serde(default)]
+    pub spring_length: f64,
+    /// Gap kept between the boxes of nodes that set a `half_extent`.
+    #[serde(default)]
+    pub collision_padding: f64,
+    /// Strength of the pull on each node toward the anchored root of its component.
+    ///
+    /// The pull grows with distance from the root, keeping a component's nodes gathered around it.
+    /// Components without an anchored node aren't affected. 0.0 disables it.
+    #[serde(default)]
+    pub component_gravity: f64,
+    /// Strength of the pull on each component toward the origin.
+    ///
+    /// The pull grows with the distance of the component's centroid from the origin and moves the whole
+    /// component, its anchored nodes included, keeping unconnected components from drifting apart. 0.0
+    /// disables it.
+    #[serde(default)]
+    pub center_gravity: f64,
+    /// Accuracy of the Barnes–Hut approximation of node repulsion.
+    ///
+    /// When set, groups of nodes whose size divided by their distance is below theta repel as a single
+    /// node, making each update roughly O(n log n) instead of O(n²). Lower values are more accurate and
+    /// 0.0 computes every pair. `None` computes every pair without building the quadtree. Parameters
+    /// serialized before this field existed load with a theta of 0.5.
+    #[serde(default = "default_barnes_hut_theta")]
+    pub barnes_hut_theta: Option<f64>,
+    /// Seed for the offsets returned by [ForceGraph::random_offset].
+    ///
+    /// When set, a graph built with the same calls always produces the same layout. `None` seeds the
+    /// offsets randomly.
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl Default for SimulationParameters {
@@ -102,6 +147,38 @@ impl Default for SimulationParameters {
             node_speed: 7000.0,
             damping_factor: 0.95,
             min_attract_distance: 0.,
+            spring_length: 0.,
+            collision_padding: 0.,
+            component_gravity: 0.,
+            center_gravity: 0.,
+            barnes_hut_theta: None,
+            seed: None,
+        }
+    }
+}
+
+/// How the nodes of a [ForceGraph] are arranged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum LayoutMode {
+    /// Nodes are positioned by the force simulation.
+    #[default]
+    Force,
+    /// A tidy tree growing downward from each root.
+    TopDown,
+    /// A tidy tree growing rightward from each root.
+    LeftRight,
+    /// Rings around each root, one per depth.
+    Radial,
+}
+
+impl LayoutMode {
+    /// The mode following this one, wrapping back around to [LayoutMode::Force].
+    pub fn next(&self) -> LayoutMode {
+        match self {
+            LayoutMode::Force => LayoutMode::TopDown,
+            LayoutMode::TopDown => LayoutMode::LeftRight,
+            LayoutMode::LeftRight => LayoutMode::Radial,
+            LayoutMode::Radial => LayoutMode::Force,
         }
     }
 }
@@ -120,7 +197,19 @@ pub struct NodeData<UserNodeData = ()> {
     /// Distance at which the repelling node force falls off.
     pub repel_distance: f64,
     /// Whether the node is fixed to its current position.
+    ///
+    /// Anchored nodes are only moved by `center_gravity`, which carries them along with their component.
     pub is_anchor: bool,
+    /// Whether the node is hidden.
+    ///
+    /// Hidden nodes are left out of the simulation. They neither move nor exert forces on other nodes.
+    #[serde(default)]
+    pub is_hidden: bool,
+    /// Half the width and height of the node's box.
+    ///
+    /// Nodes with a box are pushed apart whenever their boxes overlap. Nodes without one only repel.
+    #[serde(default)]
+    pub half_extent: Option<(f64, f64)>,
     /// Arbitrary user data.
     ///
     /// Defaults to `()` if not specified.
@@ -138,6 +227,8 @@ where
             mass: 10.,
             repel_distance: 40.,
             is_anchor: false,
+            is_hidden: false,
+            half_extent: None,
             user_data: Default::default(),
         }
     }
@@ -146,6 +237,14 @@ where
 /// Stores data associated with an edge that can be modified by the user.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EdgeData<UserEdgeData = ()> {
+    /// The rest length of the edge. Falls back to `spring_length` in [SimulationParameters] if not set.
+    #[serde(default)]
+    pub length: Option<f64>,
+    /// The stiffness of the edge. Falls back to `force_spring` in [SimulationParameters] if not set.
+    ///
+    /// Stiffer edges pull their nodes together harder when stretched.
+    #[serde(default)]
+    pub stiffness: Option<f64>,
     /// Arbitrary user data.
     ///
     /// Defaults to `()` if not specified.
@@ -158,6 +257,8 @@ where
 {
     fn default() -> Self {
         EdgeData {
+            length: None,
+            stiffness: None,
             user_data: Default::default(),
         }
     }
@@ -168,7 +269,11 @@ where
 pub struct ForceGraph<UserNodeData = (), UserEdgeData = ()> {
     pub parameters: SimulationParameters,
     graph: StableUnGraph<Node<UserNodeData>, EdgeData<UserEdgeData>>,
-    node_indices: HashSet<DefaultNodeIdx>,
+    node_indices: BTreeSet<DefaultNodeIdx>,
+    //State of the generator behind random_offset. It's saved so that a reloaded graph continues the
+    // same sequence.
+    #[serde(default)]
+    rng_state: Option<u64>,
     #[serde(skip)]
     tarjan_scc: TarjanScc<NodeIndex>,
 }
@@ -181,6 +286,7 @@ impl<UserNodeData: std::fmt::Debug + std::marker::Sync + std::marker::Send + Clo
             parameters: self.parameters.clone(),
             graph: self.graph.clone(),
             node_indices: self.node_indices.clone(),
+            rng_state: self.rng_state,
             tarjan_scc,
         }
     }
@@ -199,7 +305,8 @@ impl<UserNodeData: std::fmt::Debug + std::marker::Sync + std::marker::Send, User
             parameters,
             graph: StableUnGraph::default(),
             // node_indices: Default::default(),
-            node_indices: HashSet::new(),
+            node_indices: BTreeSet::new(),
+            rng_state: None,
             tarjan_scc: TarjanScc::new(),
         }
     }
@@ -257,22 +364,24 @@ impl<UserNodeData: std::fmt::Debug + std::marker::Sync + std::marker::Send, User
         }
         if from != root {
             //Passed node is a non-root member of its component
-            //Get path from passed node to root
-            let paths: Vec<_> = all_simple_paths::<Vec<_>, _>(&self.graph,
-                from, 
-                root, 
-                0, 
-                None).collect();
-            //Get first step on path from node to root and store is as remainder
-            remainder = Some(paths[0][1]);
-            //Add node to the removal list
-            removal_set.insert(from);
-            //Step the search forward
-            bfs.next(&self.graph);
-            //Remove the remainder from the search to constrict it to only descendant nodes
-            bfs.stack.remove(bfs.stack.iter().position(|v| {*v == remainder.unwrap()}).unwrap());
+            //Walk out from the root without passing through the node. Everything reached this way stays
+            // behind. Cross-links can give a node several paths to the root, so no single path is pruned.
+            let mut kept: HashSet<NodeIndex> = HashSet::new();
+            let mut root_bfs = petgraph::visit::Bfs::new(&self.graph, root);
+            root_bfs.discovered.visit(from);
+            while let Some(next) = root_bfs.next(&self.graph) {
+                kept.insert(next);
+                //The first neighbor of the passed node reached from the root is its first step toward it
+                if remainder.is_none() && self.graph.contains_edge(next, from) {
+                    remainder = Some(next);
+                }
+            }
             //Get all descendants and add them to the removal set
-            while let Some(next) = bfs.next(&self.graph) {removal_set.insert(next);}
+            while let Some(next) = bfs.next(&self.graph) {
+                if !kept.contains(&next) {
+                    removal_set.insert(next);
+                }
+            }
         } else {
             //Collect the entire component into the removal set 
             while let Some(next) = bfs.next(&self.graph) {removal_set.insert(next);}
@@ -352,39 +461,357 @@ impl<UserNodeData: std::fmt::Debug + std::marker::Sync + std::marker::Send, User
             return 0.;
         }
 
+        //Forces are accumulated from the node positions at the start of the step and only then integrated,
+        // so the result doesn't depend on the order the nodes are visited in. node_indices is ordered, which
+        // also keeps the order that each node's forces are summed in the same from step to step.
+        let indices = self.node_indices.iter()
+            .filter(|idx| !self.graph[**idx].data.is_hidden)
+            .cloned()
+            .collect::<Vec<DefaultNodeIdx>>();
+
+        //Barnes–Hut repulsion is calculated from a quadtree of the node positions at the start of the step
+        let quadtree = self.parameters.barnes_hut_theta.map(|_| {
+            let bodies = indices.iter().map(|idx| body_of(&self.graph[*idx])).collect::<Vec<Body>>();
+            QuadTree::new(&bodies)
+        });
+
+        let components = if self.parameters.component_gravity > 0. || self.parameters.center_gravity > 0. {
+            self.components(&indices)
+        } else {
+            vec![]
+        };
+        let roots = if self.parameters.component_gravity > 0. {
+            self.component_roots(&components)
+        } else {
+            HashMap::new()
+        };
+        let center_pulls = if self.parameters.center_gravity > 0. {
+            self.center_pulls(&components)
+        } else {
+            HashMap::new()
+        };
+
+        #[cfg(feature = "parallel")]
+        let forces = indices.par_iter()
+            .map(|idx| self.net_force(*idx, &indices, quadtree.as_ref(), &roots, &center_pulls))
+            .collect::<Vec<(f64, f64)>>();
+        #[cfg(not(feature = "parallel"))]
+        let forces = indices.iter()
+            .map(|idx| self.net_force(*idx, &indices, quadtree.as_ref(), &roots, &center_pulls))
+            .collect::<Vec<(f64, f64)>>();
+
+        let start_positions = indices.iter()
+            .map(|idx| (self.graph[*idx].x(), self.graph[*idx].y()))
+            .collect::<Vec<(f64, f64)>>();
+        let mut movements = vec![0.; indices.len()];
+        for ((idx, (fx, fy)), movement) in indices.iter().zip(forces).zip(movements.iter_mut()) {
+            let node = &mut self.graph[*idx];
+            node.apply_clamped_force(fx, fy, dt);
+            *movement = node.update(dt, &self.parameters);
+        }
+
+        //A node held apart from another is pushed back about as far as the forces moved it, so its movement
+        // is measured from where it started the step.
+        let moved = self.resolve_collisions(&indices);
+        for (slot, movement) in movements.iter_mut().enumerate() {
+            if moved[slot] {
+                let node = &self.graph[indices[slot]];
+                let (x, y) = start_positions[slot];
+                *movement = (node.x() - x).abs().max((node.y() - y).abs());
+            }
+        }
+
         let mut largest_movement = 0.;
+        for movement in movements {
+            if movement > largest_movement {
+                largest_movement = movement;
+            }
+        }
+        largest_movement
+    }
 
-        for (n1_idx_i, n1_idx) in self.node_indices.iter().enumerate() {
-            let mut edges = self.graph.neighbors(*n1_idx).detach();
-            let mut movement = 0.;
-            while let Some(n2_idx) = edges.next_node(&self.graph) {
-                let (n1, n2) = self.graph.index_twice_mut(*n1_idx, n2_idx);
-                let f = attract_nodes(n1, n2, &self.parameters);
-                n1.apply_force(f.0, f.1, dt, &self.parameters);
+    //Pushes apart the given nodes wherever their boxes overlap. Velocity toward a collision is removed so
+    // that nodes held apart come to rest. Returns which of the nodes were moved.
+    fn resolve_collisions(&mut self, indices: &[DefaultNodeIdx]) -> Vec<bool> {
+        let padding = self.parameters.collision_padding / 2.;
+        let mut moved = vec![false; indices.len()];
+        for _ in 0..COLLISION_PASSES {
+            let boxes = indices.iter().enumerate().filter_map(|(slot, idx)| {
+                let data = &self.graph[*idx].data;
+                data.half_extent.map(|(half_width, half_height)| NodeBox {
+                    slot,
+                    x: data.x,
+                    y: data.y,
+                    half_width: half_width + padding,
+                    half_height: half_height + padding,
+                    is_anchor: data.is_anchor,
+                })
+            }).collect::<Vec<NodeBox>>();
+            if boxes.len() < 2 {
+                break;
+            }
+            let mut overlapping = false;
+            for (slot, (cx, cy)) in separate(&boxes, indices.len()).into_iter().enumerate() {
+                if cx == 0. && cy == 0. {
+                    continue;
+                }
+                overlapping = true;
+                moved[slot] = true;
+                let node = &mut self.graph[indices[slot]];
+                node.data.x += cx;
+                node.data.y += cy;
+                if cx * node.vx < 0. {
+                    node.vx = 0.;
+                }
+                if cy * node.vy < 0. {
+                    node.vy = 0.;
+                }
+            }
+            if !overlapping {
+                break;
             }
+        }
+        moved
+    }
 
-            for n2_idx in self.node_indices.iter().skip(n1_idx_i + 1) {
-                let (n1, n2) = self.graph.index_twice_mut(*n1_idx, *n2_idx);
-                let f = repel_nodes(n1, n2, &self.parameters);
-                if !n1.data.is_anchor {
-                    n1.apply_force(f.0, f.1, dt, &self.parameters);
+    /// Runs the simulation in steps of [FIXED_STEP_DELTA] until no node moves more than `threshold` in a
+    /// step.
+    ///
+    /// Returns the number of steps taken, or `None` if the graph hadn't settled after `max_steps`. Since
+    /// the step doesn't depend on frame timing, a seeded graph always settles into the same layout.
+    pub fn run_until_settled(&mut self, max_steps: usize, threshold: f64) -> Option<usize> {
+        (1..=max_steps).find(|_| self.update(FIXED_STEP_DELTA) < threshold)
+    }
+
+    /// Returns a pseudo-random offset between -0.5 and 0.5 on each axis.
+    ///
+    /// Use this to place new nodes near, but not on top of, an existing node. The offsets follow
+    /// `parameters.seed` when it's set.
+    pub fn random_offset(&mut self) -> (f64, f64) {
+        let seed = self.parameters.seed;
+        let state = self.rng_state.get_or_insert_with(|| {
+            seed.unwrap_or_else(|| std::collections::hash_map::RandomState::new().build_hasher().finish())
+        });
+        let x = split_mix(state);
+        let y = split_mix(state);
+        (unit_interval(x) - 0.5, unit_interval(y) - 0.5)
+    }
+
+    //Groups the given nodes into components by walking the edges between them.
+    fn components(&self, indices: &[DefaultNodeIdx]) -> Vec<Vec<DefaultNodeIdx>> {
+        let mut components = vec![];
+        let mut visited: HashSet<DefaultNodeIdx> = HashSet::new();
+        for start in indices {
+            if !visited.insert(*start) {
+                continue;
+            }
+            let mut component = vec![*start];
+            let mut i = 0;
+            while i < component.len() {
+                for neighbor in self.graph.neighbors(component[i]) {
+                    if !self.graph[neighbor].data.is_hidden && visited.insert(neighbor) {
+                        component.push(neighbor);
+                    }
                 }
-                if !n2.data.is_anchor {
-                    n2.apply_force(-f.0, -f.1, dt, &self.parameters);
+                i += 1;
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    //Maps each node to the anchored root of its component. A component with several anchored nodes uses
+    // the one with the lowest index.
+    fn component_roots(&self, components: &[Vec<DefaultNodeIdx>]) -> HashMap<DefaultNodeIdx, DefaultNodeIdx> {
+        let mut roots: HashMap<DefaultNodeIdx, DefaultNodeIdx> = HashMap::new();
+        for component in components {
+            if let Some(root) = component.iter().filter(|idx| self.graph[**idx].data.is_anchor).min() {
+                for idx in component {
+                    roots.insert(*idx, *root);
                 }
             }
+        }
+        roots
+    }
 
+    //Maps each node to the pull of center gravity on its component. Every node of a component gets the
+    // same pull, found from the component's centroid, so the component moves toward the origin as a whole
+    // instead of being stretched toward it.
+    fn center_pulls(&self, components: &[Vec<DefaultNodeIdx>]) -> HashMap<DefaultNodeIdx, (f64, f64)> {
+        let mut pulls: HashMap<DefaultNodeIdx, (f64, f64)> = HashMap::new();
+        for component in components {
+            let count = component.len() as f64;
+            let cx = component.iter().map(|idx| self.graph[*idx].x()).sum::<f64>() / count;
+            let cy = component.iter().map(|idx| self.graph[*idx].y()).sum::<f64>() / count;
+            let pull = (
+                clamp_force(-cx * self.parameters.center_gravity, &self.parameters),
+                clamp_force(-cy * self.parameters.center_gravity, &self.parameters),
+            );
+            for idx in component {
+                pulls.insert(*idx, pull);
+            }
+        }
+        pulls
+    }
 
-            let n1 = &mut self.graph[*n1_idx];
-            if !n1.data.is_anchor {
-                movement = n1.update(dt, &self.parameters);
+    //Sums the attraction, repulsion, and gravity acting on a node. Each force is clamped to force_max
+    // before it's added. Anchored nodes are only moved by center gravity, which carries them along with
+    // the rest of their component.
+    fn net_force(
+        &self,
+        idx: DefaultNodeIdx,
+        indices: &[DefaultNodeIdx],
+        quadtree: Option<&QuadTree>,
+        roots: &HashMap<DefaultNodeIdx, DefaultNodeIdx>,
+        center_pulls: &HashMap<DefaultNodeIdx, (f64, f64)>,
+    ) -> (f64, f64) {
+        let node = &self.graph[idx];
+        let (mut fx, mut fy) = center_pulls.get(&idx).cloned().unwrap_or((0., 0.));
+        if node.data.is_anchor {
+            return (fx, fy);
+        }
+        for edge in self.graph.edges(idx) {
+            let neighbor_idx = if edge.source() == idx { edge.target() } else { edge.source() };
+            let neighbor = &self.graph[neighbor_idx];
+            if neighbor.data.is_hidden {
+                continue;
+            }
+            let f = attract_nodes(node, neighbor, edge.weight(), &self.parameters);
+            fx += clamp_force(f.0, &self.parameters);
+            fy += clamp_force(f.1, &self.parameters);
+        }
+        if let (Some(quadtree), Some(theta)) = (quadtree, self.parameters.barnes_hut_theta) {
+            let f = quadtree.repulsion_on(&body_of(node), theta, self.parameters.force_charge, self.parameters.force_max);
+            fx += f.0;
+            fy += f.1;
+        } else {
+            for other_idx in indices {
+                if *other_idx == idx {
+                    continue;
+                }
+                let f = repel_nodes(node, &self.graph[*other_idx], &self.parameters);
+                fx += clamp_force(f.0, &self.parameters);
+                fy += clamp_force(f.1, &self.parameters);
             }
+        }
+        if let Some(root) = roots.get(&idx) {
+            let root = &self.graph[*root];
+            fx += clamp_force((root.x() - node.x()) * self.parameters.component_gravity, &self.parameters);
+            fy += clamp_force((root.y() - node.y()) * self.parameters.component_gravity, &self.parameters);
+        }
+        (fx, fy)
+    }
 
-            if movement > largest_movement {
-                largest_movement = movement;
+    /// Computes the positions of a tree layout grown from each of the given roots.
+    ///
+    /// Each component is walked breadth-first from its root, visiting neighbors in index order, so
+    /// cross-links don't affect the result and the same graph always produces the same positions. Siblings
+    /// are packed as tightly as their subtrees allow (Reingold–Tilford) and parents are centered over
+    /// their children. Roots keep their current position. `extent` returns the (width, height) of a node
+    /// and `gap` is the space left between neighboring nodes and between levels. Hidden nodes are left
+    /// out of the returned positions. [LayoutMode::Force] returns no positions.
+    pub fn compute_tree_layout<F: Fn(&Node<UserNodeData>) -> (f64, f64)>(
+        &self,
+        roots: &[DefaultNodeIdx],
+        mode: LayoutMode,
+        gap: f64,
+        extent: F,
+    ) -> HashMap<DefaultNodeIdx, (f64, f64)> {
+        let mut positions: HashMap<DefaultNodeIdx, (f64, f64)> = HashMap::new();
+        if mode == LayoutMode::Force {
+            return positions;
+        }
+        let mut visited: HashSet<DefaultNodeIdx> = HashSet::new();
+        for root in roots {
+            if !self.graph.contains_node(*root) || self.graph[*root].data.is_hidden || visited.contains(root) {
+                continue;
+            }
+            //Build a spanning tree of the root's component
+            let mut children: HashMap<DefaultNodeIdx, Vec<DefaultNodeIdx>> = HashMap::new();
+            let mut depths: HashMap<DefaultNodeIdx, usize> = HashMap::new();
+            let mut queue = std::collections::VecDeque::new();
+            visited.insert(*root);
+            depths.insert(*root, 0);
+            queue.push_back(*root);
+            while let Some(idx) = queue.pop_front() {
+                let mut neighbors = self.graph.neighbors(idx).collect::<Vec<_>>();
+                neighbors.sort();
+                for neighbor in neighbors {
+                    if self.graph[neighbor].data.is_hidden || visited.contains(&neighbor) {
+                        continue;
+                    }
+                    visited.insert(neighbor);
+                    depths.insert(neighbor, depths[&idx] + 1);
+                    children.entry(idx).or_default().push(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+
+            //Extents along the breadth of the tree (across siblings) and its depth (across levels)
+            let mut breadths: HashMap<DefaultNodeIdx, f64> = HashMap::new();
+            let max_depth = depths.values().cloned().max().unwrap_or(0);
+            let mut level_extents = vec![0.; max_depth + 1];
+            for (idx, depth) in &depths {
+                let (width, height) = extent(&self.graph[*idx]);
+                let (breadth, length) = if mode == LayoutMode::LeftRight { (height, width) } else { (width, height) };
+                breadths.insert(*idx, breadth);
+                if length > level_extents[*depth] {
+                    level_extents[*depth] = length;
+                }
+            }
+            let mut level_offsets = vec![0.; max_depth + 1];
+            for depth in 1..=max_depth {
+                level_offsets[depth] = level_offsets[depth - 1] + level_extents[depth - 1] / 2. + gap + level_extents[depth] / 2.;
+            }
+
+            let subtree = tidy_subtree(*root, &children, &breadths, gap);
+            let (root_x, root_y) = (self.graph[*root].x(), self.graph[*root].y());
+            //Radial layouts wrap the breadth of the tree around a circle. The rings are spread out far enough
+            // that the outermost one fits the whole breadth.
+            let left = subtree.contour.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+            let right = subtree.contour.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max);
+            let circumference = right - left + gap;
+            let ring_scale = if max_depth > 0 {
+                (circumference / (std::f64::consts::TAU * level_offsets[max_depth])).max(1.)
+            } else {
+                1.
+            };
+            for (idx, x) in subtree.nodes {
+                let depth_offset = level_offsets[depths[&idx]];
+                let position = match mode {
+                    LayoutMode::TopDown => (root_x + x, root_y + depth_offset),
+                    LayoutMode::LeftRight => (root_x + depth_offset, root_y + x),
+                    _ => {
+                        let angle = std::f64::consts::TAU * (x - left) / circumference;
+                        let radius = depth_offset * ring_scale;
+                        (root_x + radius * angle.cos(), root_y + radius * angle.sin())
+                    },
+                };
+                positions.insert(idx, position);
+            }
+        }
+        positions
+    }
+
+    /// Moves each node in `positions` the given fraction of the way toward its position.
+    ///
+    /// Used in place of [ForceGraph::update] to animate nodes into a computed layout. Node velocities are
+    /// cleared so that the simulation starts from rest when it resumes. Returns the largest movement (x or
+    /// y) that any node has undergone.
+    pub fn approach_positions(&mut self, positions: &HashMap<DefaultNodeIdx, (f64, f64)>, fraction: f64) -> f64 {
+        let mut largest_movement: f64 = 0.;
+        for (idx, (x, y)) in positions {
+            if let Some(node) = self.graph.node_weight_mut(*idx) {
+                let dx = (x - node.data.x) * fraction;
+                let dy = (y - node.data.y) * fraction;
+                node.data.x += dx;
+                node.data.y += dy;
+                node.vx = 0.;
+                node.vy = 0.;
+                largest_movement = largest_movement.max(dx.abs()).max(dy.abs());
             }
         }
-        return largest_movement;
+        largest_movement
     }
 
     /// Processes each node with a user-defined callback `cb`.
@@ -454,9 +881,10 @@ impl<UserNodeData> Node<UserNodeData> where UserNodeData: std::fmt::Debug {
         self.index
     }
 
-    fn apply_force(&mut self, fx: f64, fy: f64, dt: f64, parameters: &SimulationParameters) {
-        self.ax += fx.max(-parameters.force_max).min(parameters.force_max) * dt;
-        self.ay += fy.max(-parameters.force_max).min(parameters.force_max) * dt;
+    //Applies a sum of forces that were each clamped to force_max when they were calculated.
+    fn apply_clamped_force(&mut self, fx: f64, fy: f64, dt: f64) {
+        self.ax += fx * dt;
+        self.ay += fy * dt;
     }
 
     //Returns the largest movement (x or y) that the node undergoes.
@@ -467,15 +895,32 @@ impl<UserNodeData> Node<UserNodeData> where UserNodeData: std::fmt::Debug {
         self.data.y += self.vy * dt;
         self.ax = 0.0;
         self.ay = 0.0;
-        if (self.vx * dt) > (self.vy * dt) {
-            return self.vx * dt;
-        } else {
-            return self.vy * dt;
-        }
+        (self.vx * dt).abs().max((self.vy * dt).abs())
     }
 }
 
-fn attract_nodes<D>(n1: &Node<D>, n2: &Node<D>, parameters: &SimulationParameters) -> (f64, f64) {
+fn default_barnes_hut_theta() -> Option<f64> {
+    Some(0.5)
+}
+
+//Advances a SplitMix64 generator and returns its next output
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+fn unit_interval(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn clamp_force(f: f64, parameters: &SimulationParameters) -> f64 {
+    f.max(-parameters.force_max).min(parameters.force_max)
+}
+
+fn attract_nodes<D, E>(n1: &Node<D>, n2: &Node<D>, edge: &EdgeData<E>, parameters: &SimulationParameters) -> (f64, f64) {
     let mut dx = n2.data.x - n1.data.x;
     let mut dy = n2.data.y - n1.data.y;
 
@@ -488,7 +933,9 @@ fn attract_nodes<D>(n1: &Node<D>, n2: &Node<D>, parameters: &SimulationParameter
     dx /= distance;
     dy /= distance;
 
-    let strength = parameters.force_spring * distance * 0.5;
+    let length = edge.length.unwrap_or(parameters.spring_length);
+    let stiffness = edge.stiffness.unwrap_or(parameters.force_spring);
+    let strength = stiffness * (distance - length) * 0.5;
     (dx * strength, dy * strength)
 }
 
@@ -513,13 +960,77 @@ fn repel_nodes_coulomb<D>(n1: &Node<D>, n2: &Node<D>, parameters: &SimulationPar
     (dx * strength, dy * strength)
 }
 
+//A laid out subtree. Node positions are along the breadth of the tree, relative to the subtree root.
+// The contour holds the leftmost and rightmost extents of the subtree at each depth below its root.
+struct TidySubtree {
+    nodes: Vec<(DefaultNodeIdx, f64)>,
+    contour: Vec<(f64, f64)>,
+}
+
+fn tidy_subtree(
+    idx: DefaultNodeIdx,
+    children: &HashMap<DefaultNodeIdx, Vec<DefaultNodeIdx>>,
+    breadths: &HashMap<DefaultNodeIdx, f64>,
+    gap: f64,
+) -> TidySubtree {
+    let half_breadth = breadths[&idx] / 2.;
+    let mut nodes = vec![(idx, 0.)];
+    let mut contour = vec![(-half_breadth, half_breadth)];
+    if let Some(kids) = children.get(&idx) {
+        let mut merged: Vec<(f64, f64)> = vec![];
+        let mut child_offsets: Vec<f64> = vec![];
+        let mut child_nodes: Vec<(DefaultNodeIdx, f64)> = vec![];
+        for child in kids {
+            let subtree = tidy_subtree(*child, children, breadths, gap);
+            //Push the subtree right until it clears everything placed before it at every shared depth
+            let mut offset: f64 = if merged.is_empty() { 0. } else { f64::NEG_INFINITY };
+            for (placed, next) in merged.iter().zip(subtree.contour.iter()) {
+                offset = offset.max(placed.1 - next.0 + gap);
+            }
+            for (depth, (left, right)) in subtree.contour.iter().enumerate() {
+                if depth < merged.len() {
+                    merged[depth].0 = merged[depth].0.min(left + offset);
+                    merged[depth].1 = right + offset;
+                } else {
+                    merged.push((left + offset, right + offset));
+                }
+            }
+            child_nodes.extend(subtree.nodes.into_iter().map(|(idx, x)| (idx, x + offset)));
+            child_offsets.push(offset);
+        }
+        //Center the parent over its first and last children
+        let center = (child_offsets[0] + child_offsets[child_offsets.len() - 1]) / 2.;
+        nodes.extend(child_nodes.into_iter().map(|(idx, x)| (idx, x - center)));
+        contour.extend(merged.into_iter().map(|(left, right)| (left - center, right - center)));
+    }
+    TidySubtree { nodes, contour }
+}
+
 //Repel using a logistic function: c/(1+e^(10(x-k/2)/k)) where c=force, k=distance
 fn repel_nodes<D>(n1: &Node<D>, n2: &Node<D>, parameters: &SimulationParameters) -> (f64, f64) {
-    let mut dx = n2.data.x - n1.data.x;
-    let mut dy = n2.data.y - n1.data.y;
-
     // let k = 230.;
-    let k = (n1.data.repel_distance/10.*n1.data.mass)+(n2.data.repel_distance/10.*n2.data.mass);
+    let k = repel_term(&n1.data) + repel_term(&n2.data);
+    repel_force(n1.data.x, n1.data.y, n2.data.x, n2.data.y, k, parameters.force_charge)
+}
+
+//Each node's share of the distance at which the logistic repulsion falls off
+fn repel_term<D>(data: &NodeData<D>) -> f64 {
+    data.repel_distance / 10. * data.mass
+}
+
+fn body_of<D>(node: &Node<D>) -> Body {
+    Body {
+        idx: node.index,
+        x: node.data.x,
+        y: node.data.y,
+        repel_term: repel_term(&node.data),
+    }
+}
+
+//The logistic repulsion acting on a node at (x1, y1) from a node at (x2, y2)
+fn repel_force(x1: f64, y1: f64, x2: f64, y2: f64, k: f64, force_charge: f64) -> (f64, f64) {
+    let mut dx = x2 - x1;
+    let mut dy = y2 - y1;
 
     let distance = if dx == 0.0 && dy == 0.0 {
         1.0
@@ -530,7 +1041,7 @@ fn repel_nodes<D>(n1: &Node<D>, n2: &Node<D>, parameters: &SimulationParameters)
     dx /= distance;
     dy /= distance;
 
-    let strength = -parameters.force_charge / (1.+f64::exp((10.*(distance-(k/2.)))/k));
+    let strength = -force_charge / (1.+f64::exp((10.*(distance-(k/2.)))/k));
     (dx * strength, dy * strength)
 }
 
@@ -558,7 +1069,7 @@ mod test {
     fn test_user_data() {
         let mut graph = ForceGraph::new(Default::default());
 
-        #[derive(Default)]
+        #[derive(Default, Debug)]
         struct UserNodeData {}
         #[derive(Default)]
         struct UserEdgeData {}
@@ -580,8 +1091,335 @@ mod test {
             n1_idx,
             n2_idx,
             EdgeData {
+                length: None,
+                stiffness: None,
                 user_data: UserEdgeData {},
             },
         );
     }
+
+    #[test]
+    fn test_descendant_tree_with_cross_link() {
+        let mut graph = <ForceGraph>::new(Default::default());
+        let root = graph.add_node(NodeData { is_anchor: true, ..Default::default() });
+        let a = graph.add_node(Default::default());
+        let b = graph.add_node(Default::default());
+        let c = graph.add_node(Default::default());
+        let d = graph.add_node(Default::default());
+        graph.add_edge(root, a, Default::default());
+        graph.add_edge(a, b, Default::default());
+        graph.add_edge(b, c, Default::default());
+        graph.add_edge(root, d, Default::default());
+        //Cross-link keeps c attached to the root through d
+        graph.add_edge(c, d, Default::default());
+
+        let (removal_set, remainder) = graph.get_node_descendant_tree(a, root);
+        assert_eq!(removal_set, vec![a].into_iter().collect());
+        assert_eq!(remainder, Some(root));
+
+        let (removal_set, remainder) = graph.get_node_descendant_tree(b, root);
+        assert_eq!(removal_set, vec![b].into_iter().collect());
+        assert_eq!(remainder, Some(a));
+
+        graph.graph.remove_edge(graph.graph.find_edge(c, d).unwrap());
+        let (removal_set, _) = graph.get_node_descendant_tree(b, root);
+        assert_eq!(removal_set, vec![b, c].into_iter().collect());
+    }
+
+    #[test]
+    fn test_tree_layout() {
+        let mut graph = <ForceGraph>::new(Default::default());
+        let root = graph.add_node(NodeData { is_anchor: true, ..Default::default() });
+        let a = graph.add_node(NodeData { x: 30., y: -5., ..Default::default() });
+        let b = graph.add_node(NodeData { x: -20., y: 8., ..Default::default() });
+        let a1 = graph.add_node(Default::default());
+        let a2 = graph.add_node(Default::default());
+        graph.add_edge(root, a, Default::default());
+        graph.add_edge(root, b, Default::default());
+        graph.add_edge(a, a1, Default::default());
+        graph.add_edge(a, a2, Default::default());
+        //Cross-link that shouldn't change the tree
+        graph.add_edge(a2, b, Default::default());
+        let extent = |_: &Node| (10., 10.);
+
+        let positions = graph.compute_tree_layout(&[root], LayoutMode::TopDown, 10., extent);
+        assert_eq!(positions[&root], (0., 0.));
+        assert_eq!(positions[&a].1, 20.);
+        assert_eq!(positions[&a1].1, 40.);
+        //Parents are centered over their children and siblings are a gap apart
+        assert_eq!(positions[&a].0, (positions[&a1].0 + positions[&a2].0) / 2.);
+        assert_eq!(positions[&a2].0 - positions[&a1].0, 20.);
+        assert_eq!(positions[&b].0 - positions[&a].0, 20.);
+        assert_eq!(positions[&root].0, (positions[&a].0 + positions[&b].0) / 2.);
+
+        let left_right = graph.compute_tree_layout(&[root], LayoutMode::LeftRight, 10., extent);
+        for (idx, (x, y)) in &positions {
+            assert_eq!(left_right[idx], (*y, *x));
+        }
+
+        assert!(graph.compute_tree_layout(&[root], LayoutMode::Force, 10., extent).is_empty());
+
+        graph.graph[a1].data.is_hidden = true;
+        let positions = graph.compute_tree_layout(&[root], LayoutMode::Radial, 10., extent);
+        assert!(!positions.contains_key(&a1));
+        assert_eq!(positions.len(), 4);
+    }
+
+    #[test]
+    fn test_approach_positions() {
+        let mut graph = <ForceGraph>::new(Default::default());
+        let n1 = graph.add_node(NodeData { x: 0., y: 0., ..Default::default() });
+        let mut positions = HashMap::new();
+        positions.insert(n1, (100., -50.));
+
+        assert_eq!(graph.approach_positions(&positions, 0.5), 50.);
+        assert_eq!((graph.graph[n1].x(), graph.graph[n1].y()), (50., -25.));
+        for _ in 0..100 {
+            graph.approach_positions(&positions, 0.5);
+        }
+        assert!(graph.approach_positions(&positions, 0.5) < 0.001);
+    }
+
+    //Scatters nodes over a square using a fixed linear congruential generator
+    fn scattered_graph(count: usize, spread: f64, theta: Option<f64>) -> ForceGraph {
+        let mut graph = <ForceGraph>::new(SimulationParameters {
+            barnes_hut_theta: theta,
+            ..Default::default()
+        });
+        let mut seed: u64 = 12345;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64 * spread
+        };
+        for _ in 0..count {
+            let (x, y) = (next(), next());
+            graph.add_node(NodeData { x, y, repel_distance: 150., ..Default::default() });
+        }
+        graph
+    }
+
+    #[test]
+    fn test_barnes_hut_with_zero_theta_is_exact() {
+        let mut exact = scattered_graph(60, 300., None);
+        let mut barnes_hut = scattered_graph(60, 300., Some(0.));
+        exact.update(0.01);
+        barnes_hut.update(0.01);
+        for idx in exact.graph.node_indices() {
+            assert!((exact.graph[idx].x() - barnes_hut.graph[idx].x()).abs() < 1e-9);
+            assert!((exact.graph[idx].y() - barnes_hut.graph[idx].y()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_barnes_hut_approximates_repulsion() {
+        let graph = scattered_graph(400, 4000., None);
+        let bodies = graph.graph.node_weights().map(body_of).collect::<Vec<Body>>();
+        let quadtree = QuadTree::new(&bodies);
+        let (charge, max) = (1000., graph.parameters.force_max);
+        let clamp = |f: f64| f.max(-max).min(max);
+        let mut total_error = 0.;
+        let mut total_force = 0.;
+        for body in &bodies {
+            let mut exact = (0., 0.);
+            for other in &bodies {
+                if other.idx != body.idx {
+                    let f = repel_force(body.x, body.y, other.x, other.y, body.repel_term + other.repel_term, charge);
+                    exact = (exact.0 + clamp(f.0), exact.1 + clamp(f.1));
+                }
+            }
+            let approximate = quadtree.repulsion_on(body, 0.5, charge, max);
+            total_error += ((approximate.0 - exact.0).powi(2) + (approximate.1 - exact.1).powi(2)).sqrt();
+            total_force += (exact.0 * exact.0 + exact.1 * exact.1).sqrt();
+        }
+        assert!(total_error / total_force < 0.03);
+    }
+
+    #[test]
+    fn test_update_is_independent_of_visit_order() {
+        //Builds the same tree with its nodes added in the given order, so the graph indices differ
+        let build = |order: &[usize]| {
+            let reference = scattered_graph(50, 600., None);
+            let positions = reference.graph.node_indices().map(|idx| (reference.graph[idx].x(), reference.graph[idx].y())).collect::<Vec<_>>();
+            let mut graph = <ForceGraph<usize>>::new(Default::default());
+            let mut indices = vec![DefaultNodeIdx::default(); order.len()];
+            for i in order {
+                let (x, y) = positions[*i];
+                indices[*i] = graph.add_node(NodeData { x, y, repel_distance: 150., is_anchor: *i == 0, user_data: *i, ..Default::default() });
+            }
+            for i in 1..order.len() {
+                graph.add_edge(indices[(i - 1) / 3], indices[i], Default::default());
+            }
+            for _ in 0..5 {
+                graph.update(0.032);
+            }
+            let mut positions = vec![(0., 0.); order.len()];
+            graph.visit_nodes(|node| positions[node.data.user_data] = (node.x(), node.y()));
+            positions
+        };
+        let forward = build(&(0..50).collect::<Vec<usize>>());
+        let backward = build(&(0..50).rev().collect::<Vec<usize>>());
+        for (f, b) in forward.iter().zip(backward.iter()) {
+            assert!((f.0 - b.0).abs() < 1e-9);
+            assert!((f.1 - b.1).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_seeded_graphs_settle_identically() {
+        let build = || {
+            let mut graph = <ForceGraph>::new(SimulationParameters {
+                force_charge: 1000.,
+                force_spring: 4.,
+                node_speed: 3000.,
+                damping_factor: 0.5,
+                min_attract_distance: 180.,
+                seed: Some(7),
+                ..Default::default()
+            });
+            let root = graph.add_node(NodeData { is_anchor: true, ..Default::default() });
+            let mut indices = vec![root];
+            for i in 1..30 {
+                let parent = indices[(i - 1) / 3];
+                let (ox, oy) = graph.random_offset();
+                let (x, y) = (graph.graph[parent].x() + ox * 100., graph.graph[parent].y() + oy * 100.);
+                let idx = graph.add_node(NodeData { x, y, ..Default::default() });
+                graph.add_edge(parent, idx, Default::default());
+                indices.push(idx);
+            }
+            let steps = graph.run_until_settled(5000, 0.1);
+            (steps, indices.iter().map(|idx| (graph.graph[*idx].x(), graph.graph[*idx].y())).collect::<Vec<_>>())
+        };
+        let (steps, positions) = build();
+        assert!(steps.is_some());
+        assert_eq!(build(), (steps, positions));
+    }
+
+    #[test]
+    fn test_edge_length_and_stiffness() {
+        let settled_distance = |edge: EdgeData| {
+            let mut graph = <ForceGraph>::new(SimulationParameters {
+                force_charge: 1000.,
+                force_spring: 4.,
+                node_speed: 3000.,
+                damping_factor: 0.5,
+                ..Default::default()
+            });
+            let n1 = graph.add_node(NodeData { is_anchor: true, ..Default::default() });
+            let n2 = graph.add_node(NodeData { x: 50., y: 10., ..Default::default() });
+            graph.add_edge(n1, n2, edge);
+            assert!(graph.run_until_settled(20000, 0.001).is_some());
+            (graph.graph[n2].x().powi(2) + graph.graph[n2].y().powi(2)).sqrt()
+        };
+        let default = settled_distance(Default::default());
+        let long = settled_distance(EdgeData { length: Some(200.), ..Default::default() });
+        let stiff = settled_distance(EdgeData { stiffness: Some(16.), ..Default::default() });
+        assert!(long > 190.);
+        assert!(stiff < default);
+    }
+
+    #[test]
+    fn test_node_boxes_are_separated() {
+        let mut graph = <ForceGraph>::new(SimulationParameters {
+            force_charge: 1000.,
+            force_spring: 4.,
+            node_speed: 3000.,
+            damping_factor: 0.5,
+            collision_padding: 10.,
+            ..Default::default()
+        });
+        let root = graph.add_node(NodeData { is_anchor: true, half_extent: Some((100., 15.)), ..Default::default() });
+        let mut indices = vec![root];
+        for i in 1..12 {
+            let angle = i as f64;
+            let idx = graph.add_node(NodeData { x: angle.cos() * 30., y: angle.sin() * 30., half_extent: Some((100., 15.)), ..Default::default() });
+            graph.add_edge(indices[(i - 1) / 3], idx, Default::default());
+            indices.push(idx);
+        }
+        assert!(graph.run_until_settled(5000, 0.1).is_some());
+        for (i, n1) in indices.iter().enumerate() {
+            for n2 in &indices[i + 1..] {
+                let overlap_x = 210. - (graph.graph[*n1].x() - graph.graph[*n2].x()).abs();
+                let overlap_y = 40. - (graph.graph[*n1].y() - graph.graph[*n2].y()).abs();
+                assert!(overlap_x < 1. || overlap_y < 1.);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gravity() {
+        //Two free trees far from the origin and a free node tied to a distant anchored root
+        let distances_after_steps = |component_gravity: f64, center_gravity: f64| {
+            let mut graph = <ForceGraph>::new(SimulationParameters {
+                component_gravity,
+                center_gravity,
+                ..Default::default()
+            });
+            let a1 = graph.add_node(NodeData { x: -800., y: 0., ..Default::default() });
+            let a2 = graph.add_node(NodeData { x: -840., y: 20., ..Default::default() });
+            graph.add_edge(a1, a2, Default::default());
+            let b1 = graph.add_node(NodeData { x: 800., y: 0., ..Default::default() });
+            let b2 = graph.add_node(NodeData { x: 840., y: 20., ..Default::default() });
+            graph.add_edge(b1, b2, Default::default());
+            let root = graph.add_node(NodeData { x: 0., y: 2000., is_anchor: true, ..Default::default() });
+            let leaf = graph.add_node(NodeData { x: 0., y: 1000., ..Default::default() });
+            graph.add_edge(root, leaf, Default::default());
+            graph.parameters.force_spring = 0.;
+            for _ in 0..20 {
+                graph.update(0.01);
+            }
+            (graph.graph[b1].x() - graph.graph[a1].x(), graph.graph[root].y() - graph.graph[leaf].y())
+        };
+        let (free_spread, free_leaf) = distances_after_steps(0., 0.);
+        let (packed_spread, _) = distances_after_steps(0., 0.05);
+        let (_, pulled_leaf) = distances_after_steps(0.05, 0.);
+        assert!(packed_spread < free_spread);
+        assert!(pulled_leaf < free_leaf);
+    }
+
+    #[test]
+    fn test_center_gravity_moves_anchored_components() {
+        //Two trees held by anchored roots far from the origin on either side
+        let spread_after_steps = |center_gravity: f64| {
+            let mut graph = <ForceGraph>::new(SimulationParameters {
+                center_gravity,
+                ..Default::default()
+            });
+            let a = graph.add_node(NodeData { x: -800., y: 0., is_anchor: true, ..Default::default() });
+            let a_leaf = graph.add_node(NodeData { x: -840., y: 20., ..Default::default() });
+            graph.add_edge(a, a_leaf, Default::default());
+            let b = graph.add_node(NodeData { x: 800., y: 0., is_anchor: true, ..Default::default() });
+            let b_leaf = graph.add_node(NodeData { x: 840., y: 20., ..Default::default() });
+            graph.add_edge(b, b_leaf, Default::default());
+            for _ in 0..20 {
+                graph.update(0.01);
+            }
+            (graph.graph[b].x() - graph.graph[a].x(), graph.graph[b_leaf].x() - graph.graph[b].x())
+        };
+        let (free_spread, free_leaf) = spread_after_steps(0.);
+        let (packed_spread, packed_leaf) = spread_after_steps(0.05);
+        assert_eq!(free_spread, 1600.);
+        assert!(packed_spread < free_spread);
+        //The trees move as a whole rather than their leaves being pulled away from their roots
+        assert!((packed_leaf - free_leaf).abs() < 1.);
+    }
+
+    #[test]
+    fn test_hidden_nodes_are_not_simulated() {
+        let mut graph = <ForceGraph>::new(Default::default());
+        let root = graph.add_node(NodeData { is_anchor: true, ..Default::default() });
+        let a = graph.add_node(NodeData { x: 10., y: 10., ..Default::default() });
+        let hidden = graph.add_node(NodeData { x: 12., y: 12., is_hidden: true, ..Default::default() });
+        graph.add_edge(root, a, Default::default());
+        graph.add_edge(a, hidden, Default::default());
+
+        let mut with_hidden = graph.clone();
+        with_hidden.update(0.01);
+        graph.remove_node(hidden);
+        graph.update(0.01);
+
+        //The hidden node stays put and has no effect on its neighbor
+        assert_eq!((with_hidden.graph[hidden].x(), with_hidden.graph[hidden].y()), (12., 12.));
+        assert_eq!(with_hidden.graph[a].x(), graph.graph[a].x());
+        assert_eq!(with_hidden.graph[a].y(), graph.graph[a].y());
+    }
 }
@@ -0,0 +1,183 @@
+//! A quadtree used to approximate node repulsion with the Barnes–Hut method.
+//!
+//! Distant groups of nodes are treated as a single node at their center of mass, which brings the cost
+//! of a repulsion step down from O(n²) to roughly O(n log n).
+
+use crate::{repel_force, DefaultNodeIdx};
+
+//Cells stop subdividing at this depth so that nodes sharing a position don't recurse forever
+const MAX_DEPTH: usize = 24;
+
+/// A node as seen by the quadtree.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Body {
+    pub idx: DefaultNodeIdx,
+    pub x: f64,
+    pub y: f64,
+    /// The node's share of the logistic falloff distance (see `repel_nodes`).
+    pub repel_term: f64,
+}
+
+#[derive(Debug)]
+struct Cell {
+    x0: f64,
+    y0: f64,
+    size: f64,
+    count: usize,
+    sum_x: f64,
+    sum_y: f64,
+    sum_repel_term: f64,
+    //Index of the first of four consecutive child cells (NW, NE, SW, SE)
+    children: Option<usize>,
+    bodies: Vec<Body>,
+}
+
+impl Cell {
+    fn new(x0: f64, y0: f64, size: f64) -> Self {
+        Cell {
+            x0,
+            y0,
+            size,
+            count: 0,
+            sum_x: 0.,
+            sum_y: 0.,
+            sum_repel_term: 0.,
+            children: None,
+            bodies: vec![],
+        }
+    }
+
+    fn quadrant(&self, x: f64, y: f64) -> usize {
+        let half = self.size / 2.;
+        let east = if x >= self.x0 + half { 1 } else { 0 };
+        let south = if y >= self.y0 + half { 2 } else { 0 };
+        east + south
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x0 && x < self.x0 + self.size && y >= self.y0 && y < self.y0 + self.size
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct QuadTree {
+    cells: Vec<Cell>,
+}
+
+impl QuadTree {
+    /// Builds a quadtree covering all of the given bodies.
+    pub fn new(bodies: &[Body]) -> Self {
+        let mut x_min = f64::INFINITY;
+        let mut y_min = f64::INFINITY;
+        let mut x_max = f64::NEG_INFINITY;
+        let mut y_max = f64::NEG_INFINITY;
+        for body in bodies {
+            x_min = x_min.min(body.x);
+            y_min = y_min.min(body.y);
+            x_max = x_max.max(body.x);
+            y_max = y_max.max(body.y);
+        }
+        //Pad the bounds slightly so the bodies on the far edges fall inside the root cell
+        let root = if bodies.is_empty() {
+            Cell::new(0., 0., 1.)
+        } else {
+            Cell::new(x_min, y_min, (x_max - x_min).max(y_max - y_min).max(1.) * 1.001)
+        };
+        let mut tree = QuadTree { cells: vec![root] };
+        for body in bodies {
+            tree.insert(*body);
+        }
+        tree
+    }
+
+    fn insert(&mut self, body: Body) {
+        let mut cell_idx = 0;
+        let mut depth = 0;
+        loop {
+            {
+                let cell = &mut self.cells[cell_idx];
+                cell.count += 1;
+                cell.sum_x += body.x;
+                cell.sum_y += body.y;
+                cell.sum_repel_term += body.repel_term;
+            }
+            if self.cells[cell_idx].children.is_none() {
+                if self.cells[cell_idx].bodies.is_empty() || depth >= MAX_DEPTH {
+                    self.cells[cell_idx].bodies.push(body);
+                    return;
+                }
+                self.subdivide(cell_idx);
+            }
+            let first_child = self.cells[cell_idx].children.unwrap();
+            cell_idx = first_child + self.cells[cell_idx].quadrant(body.x, body.y);
+            depth += 1;
+        }
+    }
+
+    //Splits a leaf into four children and moves its bodies down into them. The leaf's totals already
+    // include its bodies, so only the children's totals are updated.
+    fn subdivide(&mut self, cell_idx: usize) {
+        let (x0, y0, half) = {
+            let cell = &self.cells[cell_idx];
+            (cell.x0, cell.y0, cell.size / 2.)
+        };
+        let first_child = self.cells.len();
+        self.cells.push(Cell::new(x0, y0, half));
+        self.cells.push(Cell::new(x0 + half, y0, half));
+        self.cells.push(Cell::new(x0, y0 + half, half));
+        self.cells.push(Cell::new(x0 + half, y0 + half, half));
+        self.cells[cell_idx].children = Some(first_child);
+        let bodies = std::mem::take(&mut self.cells[cell_idx].bodies);
+        for body in bodies {
+            let quadrant = self.cells[cell_idx].quadrant(body.x, body.y);
+            let child = &mut self.cells[first_child + quadrant];
+            child.count += 1;
+            child.sum_x += body.x;
+            child.sum_y += body.y;
+            child.sum_repel_term += body.repel_term;
+            child.bodies.push(body);
+        }
+    }
+
+    /// Returns the total repulsion acting on a body.
+    ///
+    /// A cell whose size divided by its distance from the body is below `theta` is treated as a single
+    /// node at its center of mass, using the average falloff distance of its nodes. Like the exact
+    /// calculation, each node's force is clamped to `force_max` before it's added to the total.
+    pub fn repulsion_on(&self, body: &Body, theta: f64, force_charge: f64, force_max: f64) -> (f64, f64) {
+        let mut fx = 0.;
+        let mut fy = 0.;
+        let clamp = |f: f64| f.max(-force_max).min(force_max);
+        let mut stack = vec![0];
+        while let Some(cell_idx) = stack.pop() {
+            let cell = &self.cells[cell_idx];
+            if cell.count == 0 {
+                continue;
+            }
+            if let Some(first_child) = cell.children {
+                let count = cell.count as f64;
+                let (cx, cy) = (cell.sum_x / count, cell.sum_y / count);
+                let distance = ((cx - body.x).powi(2) + (cy - body.y).powi(2)).sqrt();
+                //Cells holding the body are always opened so that it doesn't repel itself
+                if !cell.contains(body.x, body.y) && cell.size / distance < theta {
+                    let k = body.repel_term + cell.sum_repel_term / count;
+                    let f = repel_force(body.x, body.y, cx, cy, k, force_charge);
+                    fx += clamp(f.0) * count;
+                    fy += clamp(f.1) * count;
+                } else {
+                    stack.extend(first_child..first_child + 4);
+                }
+            } else {
+                for other in &cell.bodies {
+                    if other.idx == body.idx {
+                        continue;
+                    }
+                    let f = repel_force(body.x, body.y, other.x, other.y, body.repel_term + other.repel_term, force_charge);
+                    fx += clamp(f.0);
+                    fy += clamp(f.1);
+                }
+            }
+        }
+        (fx, fy)
+    }
+}